@@ -0,0 +1,293 @@
+use crate::{Currency, Date, RateError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// Tells `Bank` the time when caching provider rates. Like rate providers,
+/// clocks are `Send + Sync` so that the bank can be shared across threads.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system's wall clock.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time, so a
+/// test can keep one and hand another to `Bank::set_clock`.
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<Mutex<SystemTime>>);
+
+impl ManualClock {
+    pub fn new(now: SystemTime) -> Self {
+        ManualClock(Arc::new(Mutex::new(now)))
+    }
+    pub fn advance(&self, by: Duration) {
+        *lock(&self.0) += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *lock(&self.0)
+    }
+}
+
+/// Locks `mutex`, carrying on after a panic elsewhere: what it guards here
+/// is plain data that is never left half-updated.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A pair and the date its rate was asked for, if any.
+type CacheKey = (Currency, Currency, Option<Date>);
+
+/// Provider rates kept by `Bank` with the time they were fetched.
+pub(crate) struct RateCache<T> {
+    entries: Mutex<HashMap<CacheKey, (T, SystemTime)>>,
+    /// `None` leaves pairs without a TTL of their own uncached.
+    pub(crate) ttl: Option<Duration>,
+    pub(crate) pair_ttls: HashMap<(Currency, Currency), Duration>,
+    pub(crate) max_staleness: Option<Duration>,
+    pub(crate) clock: Box<dyn Clock>,
+}
+
+impl<T: Copy> RateCache<T> {
+    pub(crate) fn new() -> Self {
+        RateCache {
+            entries: Mutex::default(),
+            ttl: None,
+            pair_ttls: HashMap::new(),
+            max_staleness: None,
+            clock: Box::new(SystemClock),
+        }
+    }
+    pub(crate) fn clear(&self) {
+        lock(&self.entries).clear();
+    }
+    fn ttl(&self, from: Currency, to: Currency) -> Option<Duration> {
+        self.pair_ttls
+            .get(&(from, to))
+            .or_else(|| self.pair_ttls.get(&(to, from)))
+            .copied()
+            .or(self.ttl)
+    }
+    /// The cached rate while it is younger than its TTL and no older than
+    /// the maximum staleness, else a fresh one from `fetch`. When fetching
+    /// fails the expired rate is still used, as long as it is no older than
+    /// the maximum staleness.
+    pub(crate) fn get_or_fetch<F>(
+        &self,
+        from: Currency,
+        to: Currency,
+        at: Option<Date>,
+        fetch: F,
+    ) -> Result<T, RateError>
+    where
+        F: FnOnce() -> Result<T, RateError>,
+    {
+        let ttl = match self.ttl(from, to) {
+            Some(ttl) => ttl,
+            None => return fetch(),
+        };
+        let now = self.clock.now();
+        let key = (from, to, at);
+        let cached = lock(&self.entries).get(&key).map(|&(rate, fetched)| {
+            // A clock set back makes the entry look brand new.
+            let age = now.duration_since(fetched).unwrap_or_default();
+            (rate, age)
+        });
+        let too_stale = |age| self.max_staleness.filter(|&max| age > max);
+        if let Some((rate, age)) = cached {
+            if age < ttl && too_stale(age).is_none() {
+                return Ok(rate);
+            }
+        }
+        match (fetch(), cached) {
+            (Ok(rate), _) => {
+                lock(&self.entries).insert(key, (rate, now));
+                Ok(rate)
+            }
+            (Err(_), Some((rate, age))) => match too_stale(age) {
+                Some(max) => Err(RateError::Stale { age, max }),
+                None => Ok(rate),
+            },
+            (Err(error), None) => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::dec, Bank, Decimal, Hop, Money, RateProvider};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers with whatever rate the test has set, or fails when it is
+    /// `None`, counting the calls.
+    #[derive(Clone)]
+    struct Feed {
+        rate: Arc<Mutex<Option<Decimal>>>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Feed {
+        fn new(rate: &str) -> Self {
+            Feed {
+                rate: Arc::new(Mutex::new(Some(dec(rate)))),
+                calls: Arc::default(),
+            }
+        }
+        fn set(&self, rate: Option<&str>) {
+            *self.rate.lock().unwrap() = rate.map(dec);
+        }
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl RateProvider<Decimal> for Feed {
+        fn rate(
+            &self,
+            from: Currency,
+            to: Currency,
+            _: Option<Date>,
+        ) -> Result<Hop<Decimal>, RateError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let rate = self
+                .rate
+                .lock()
+                .unwrap()
+                .ok_or_else(|| RateError::Unavailable("feed down".to_string()))?;
+            Ok(Hop {
+                from,
+                to,
+                rate,
+                inverse: false,
+            })
+        }
+    }
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(60 * minutes)
+    }
+
+    fn setup(feed: &Feed) -> (Bank<Decimal>, ManualClock) {
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let mut bank = Bank::new();
+        bank.set_rate_provider(feed.clone());
+        bank.set_clock(clock.clone());
+        (bank, clock)
+    }
+
+    fn reduce(bank: &Bank<Decimal>) -> Result<Money<Decimal>, RateError> {
+        bank.reduce(Money::new(dec("10"), Currency::CHF), Currency::USD)
+            .map_err(|mut error| error.legs.remove(0).reason)
+    }
+
+    #[test]
+    fn test_bank_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        let (bank, _) = setup(&Feed::new("2"));
+        assert_send_sync(&bank);
+        assert_send_sync(&Bank::<Decimal>::new());
+    }
+
+    #[test]
+    fn test_uncached_by_default() {
+        let feed = Feed::new("2");
+        let (bank, _) = setup(&feed);
+        reduce(&bank).unwrap();
+        reduce(&bank).unwrap();
+        assert_eq!(2, feed.calls());
+    }
+
+    #[test]
+    fn test_ttl() {
+        let feed = Feed::new("2");
+        let (mut bank, clock) = setup(&feed);
+        bank.set_cache_ttl(minutes(10));
+        assert_eq!(Ok(Money::new(dec("5"), Currency::USD)), reduce(&bank));
+        feed.set(Some("4"));
+        clock.advance(minutes(9));
+        assert_eq!(Ok(Money::new(dec("5"), Currency::USD)), reduce(&bank));
+        assert_eq!(1, feed.calls());
+        clock.advance(minutes(1));
+        assert_eq!(Ok(Money::new(dec("2.5"), Currency::USD)), reduce(&bank));
+        assert_eq!(2, feed.calls());
+    }
+
+    #[test]
+    fn test_pair_ttl() {
+        let feed = Feed::new("2");
+        let (mut bank, clock) = setup(&feed);
+        bank.set_cache_ttl(minutes(60));
+        bank.set_cache_ttl_for(Currency::USD, Currency::CHF, minutes(1));
+        reduce(&bank).unwrap();
+        clock.advance(minutes(1));
+        reduce(&bank).unwrap();
+        assert_eq!(2, feed.calls());
+        bank.clear_rate_cache();
+        reduce(&bank).unwrap();
+        assert_eq!(3, feed.calls());
+    }
+
+    #[test]
+    fn test_max_staleness_within_ttl() {
+        let feed = Feed::new("2");
+        let (mut bank, clock) = setup(&feed);
+        bank.set_cache_ttl(minutes(60));
+        bank.set_max_staleness(minutes(30));
+        reduce(&bank).unwrap();
+        clock.advance(minutes(30));
+        reduce(&bank).unwrap();
+        assert_eq!(1, feed.calls());
+        clock.advance(minutes(15));
+        feed.set(Some("4"));
+        assert_eq!(Ok(Money::new(dec("2.5"), Currency::USD)), reduce(&bank));
+        assert_eq!(2, feed.calls());
+        feed.set(None);
+        clock.advance(minutes(45));
+        assert_eq!(
+            Err(RateError::Stale {
+                age: minutes(45),
+                max: minutes(30),
+            }),
+            reduce(&bank)
+        );
+    }
+
+    #[test]
+    fn test_max_staleness() {
+        let feed = Feed::new("2");
+        let (mut bank, clock) = setup(&feed);
+        bank.set_cache_ttl(minutes(10));
+        bank.set_max_staleness(minutes(30));
+        reduce(&bank).unwrap();
+        feed.set(None);
+        clock.advance(minutes(30));
+        assert_eq!(Ok(Money::new(dec("5"), Currency::USD)), reduce(&bank));
+        clock.advance(minutes(1));
+        assert_eq!(
+            Err(RateError::Stale {
+                age: minutes(31),
+                max: minutes(30),
+            }),
+            reduce(&bank)
+        );
+        assert_eq!(
+            "can't convert 10 CHF (leg 0) to USD: cached rate is 1860s old, over the 1800s limit",
+            bank.reduce(Money::new(dec("10"), Currency::CHF), Currency::USD)
+                .unwrap_err()
+                .to_string()
+        );
+        feed.set(Some("4"));
+        assert_eq!(Ok(Money::new(dec("2.5"), Currency::USD)), reduce(&bank));
+    }
+}
@@ -0,0 +1,377 @@
+use crate::{Bank, Currency, Date};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::ops::{Add, Div, Mul};
+use std::str::FromStr;
+
+const HEADER: &str = "date,from,to,rate";
+
+/// What is wrong with a row of a rate file.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CsvErrorKind<T> {
+    /// The reader failed; holds the I/O error message.
+    Io(String),
+    /// The row doesn't have exactly four fields; holds how many it has.
+    FieldCount(usize),
+    InvalidDate(String),
+    UnknownCurrency(String),
+    InvalidRate(String),
+    /// A different rate for the pair, or one stored in the other direction,
+    /// is already in the bank or earlier in the file. `from` and `to` are
+    /// the direction `existing` is stored in.
+    Conflict {
+        from: Currency,
+        to: Currency,
+        existing: T,
+    },
+}
+
+/// Returned by `Bank::load_csv`; `line` counts from 1.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CsvError<T> {
+    pub line: usize,
+    pub kind: CsvErrorKind<T>,
+}
+
+impl<T: fmt::Display> fmt::Display for CsvError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            CsvErrorKind::Io(message) => write!(f, "{}", message),
+            CsvErrorKind::FieldCount(count) => {
+                write!(f, "expected 4 fields ({}), found {}", HEADER, count)
+            }
+            CsvErrorKind::InvalidDate(date) => write!(f, "invalid date {:?}", date),
+            CsvErrorKind::UnknownCurrency(code) => write!(f, "unknown currency {:?}", code),
+            CsvErrorKind::InvalidRate(rate) => write!(f, "invalid rate {:?}", rate),
+            CsvErrorKind::Conflict { from, to, existing } => write!(
+                f,
+                "conflicts with the {}/{} rate {} already loaded",
+                from, to, existing
+            ),
+        }
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> Error for CsvError<T> {}
+
+struct Row<T> {
+    line: usize,
+    date: Option<Date>,
+    from: Currency,
+    to: Currency,
+    rate: T,
+}
+
+/// Reads every data row, skipping blank lines and an optional header. An
+/// empty date column reads as `None`; a rate must be positive.
+fn read_rows<T: Default + PartialOrd + FromStr>(
+    reader: impl Read,
+) -> Result<Vec<Row<T>>, CsvError<T>> {
+    let mut rows = vec![];
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line_number = i + 1;
+        let error = |kind| CsvError {
+            line: line_number,
+            kind,
+        };
+        let line = line.map_err(|e| error(CsvErrorKind::Io(e.to_string())))?;
+        let line = line.trim();
+        if line.is_empty() || (rows.is_empty() && line.eq_ignore_ascii_case(HEADER)) {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let (date, from, to, rate) = match fields[..] {
+            [date, from, to, rate] => (date, from, to, rate),
+            _ => return Err(error(CsvErrorKind::FieldCount(fields.len()))),
+        };
+        let currency = |code: &str| {
+            Currency::from_code(code)
+                .ok_or_else(|| error(CsvErrorKind::UnknownCurrency(code.to_string())))
+        };
+        rows.push(Row {
+            line: line_number,
+            date: match date {
+                "" => None,
+                date => Some(
+                    date.parse()
+                        .map_err(|_| error(CsvErrorKind::InvalidDate(date.to_string())))?,
+                ),
+            },
+            from: currency(from)?,
+            to: currency(to)?,
+            rate: rate
+                .parse()
+                .ok()
+                .filter(|rate| *rate > T::default())
+                .ok_or_else(|| error(CsvErrorKind::InvalidRate(rate.to_string())))?,
+        });
+    }
+    Ok(rows)
+}
+
+impl<T> Bank<T>
+where
+    T: Copy
+        + Add<Output = T>
+        + Default
+        + From<u8>
+        + Mul<Output = T>
+        + Div<Output = T>
+        + PartialOrd
+        + FromStr,
+{
+    /// Loads `date,from,to,rate` rows into the plain rate table, ignoring the
+    /// date column, and returns how many rates were added. Like `add_rate`,
+    /// a pair already stored in either direction isn't replaced: a row
+    /// repeating a stored rate is skipped and any other is a conflict. Nothing
+    /// is loaded unless the whole file is valid.
+    pub fn load_csv(&mut self, reader: impl Read) -> Result<usize, CsvError<T>> {
+        let mut rows = read_rows(reader)?;
+        for row in rows.iter_mut() {
+            row.date = None;
+        }
+        self.load_rows(rows)
+    }
+    /// Like `load_csv`, but each row is a rate effective from its date, as
+    /// with `set_rate_at`. Rows with an empty date go to the plain table, so
+    /// this reads back what `export_csv` writes.
+    pub fn load_csv_dated(&mut self, reader: impl Read) -> Result<usize, CsvError<T>> {
+        let rows = read_rows(reader)?;
+        self.load_rows(rows)
+    }
+    fn load_rows(&mut self, rows: Vec<Row<T>>) -> Result<usize, CsvError<T>> {
+        let mut loaded: HashMap<(Currency, Currency, Option<Date>), T> = HashMap::new();
+        let mut added = vec![];
+        for row in rows {
+            let stored = |from, to| {
+                loaded
+                    .get(&(from, to, row.date))
+                    .copied()
+                    .or_else(|| match row.date {
+                        Some(date) => self.history.get(&(from, to))?.get(&date).copied(),
+                        None => self.rates.get(&(from, to)).copied(),
+                    })
+            };
+            let conflict = match (stored(row.from, row.to), stored(row.to, row.from)) {
+                (Some(existing), _) if existing == row.rate => continue,
+                (Some(existing), _) => (row.from, row.to, existing),
+                (None, Some(existing)) => (row.to, row.from, existing),
+                (None, None) => {
+                    loaded.insert((row.from, row.to, row.date), row.rate);
+                    added.push(row);
+                    continue;
+                }
+            };
+            let (from, to, existing) = conflict;
+            return Err(CsvError {
+                line: row.line,
+                kind: CsvErrorKind::Conflict { from, to, existing },
+            });
+        }
+        for row in &added {
+            match row.date {
+                Some(date) => self.set_rate_at(row.from, row.to, date, row.rate),
+                None => self.set_rate(row.from, row.to, row.rate),
+            };
+        }
+        Ok(added.len())
+    }
+}
+
+impl<T: Copy + fmt::Display> Bank<T> {
+    /// Writes the plain rates, with an empty date, then every effective-dated
+    /// rate, each sorted by pair and date, under a `date,from,to,rate` header.
+    /// The format has no room for bid/ask quotes, so a pair set with
+    /// `set_bid_ask` is written as its mid rate and loads back without its
+    /// spread; the `serde` rate table keeps it.
+    pub fn export_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        let mut plain: Vec<_> = self.rates.iter().collect();
+        plain.sort_by_key(|(pair, _)| **pair);
+        for ((from, to), rate) in plain {
+            writeln!(writer, ",{},{},{}", from, to, rate)?;
+        }
+        let mut dated: Vec<_> = self
+            .history
+            .iter()
+            .flat_map(|(pair, series)| series.iter().map(move |(date, rate)| (pair, date, rate)))
+            .collect();
+        dated.sort_by_key(|(pair, date, _)| (**pair, **date));
+        for ((from, to), date, rate) in dated {
+            writeln!(writer, "{},{},{},{}", date, from, to, rate)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{date, dec};
+    use crate::{BidAsk, Decimal};
+
+    #[test]
+    fn test_load_csv() {
+        let mut bank = Bank::new();
+        let csv = "date,from,to,rate\n\
+                   2024-01-31,USD,CHF,0.9\n\
+                   \n\
+                   2024-01-31, EUR ,USD,1.08\n\
+                   2024-02-01,USD,CHF,0.9\n";
+        assert_eq!(Ok(2), bank.load_csv(csv.as_bytes()));
+        assert_eq!(
+            Some((Currency::USD, Currency::CHF, dec("0.9"))),
+            bank.rate(Currency::USD, Currency::CHF)
+        );
+        assert_eq!(
+            Some((Currency::EUR, Currency::USD, dec("1.08"))),
+            bank.rate(Currency::EUR, Currency::USD)
+        );
+        assert_eq!(
+            None,
+            bank.rate_at(Currency::USD, Currency::CHF, date("2024-01-31"))
+        );
+    }
+
+    #[test]
+    fn test_load_csv_dated() {
+        let mut bank = Bank::new();
+        let csv = "2024-01-31,USD,CHF,0.9\n\
+                   2024-02-01,USD,CHF,0.91\n\
+                   ,EUR,USD,1.08\n";
+        assert_eq!(Ok(3), bank.load_csv_dated(csv.as_bytes()));
+        assert_eq!(
+            Some((Currency::USD, Currency::CHF, dec("0.9"))),
+            bank.rate_at(Currency::USD, Currency::CHF, date("2024-01-31"))
+        );
+        assert_eq!(
+            Some((Currency::USD, Currency::CHF, dec("0.91"))),
+            bank.rate_at(Currency::USD, Currency::CHF, date("2024-02-05"))
+        );
+        assert_eq!(
+            Some((Currency::EUR, Currency::USD, dec("1.08"))),
+            bank.rate(Currency::EUR, Currency::USD)
+        );
+        assert_eq!(None, bank.rate(Currency::USD, Currency::CHF));
+    }
+
+    #[test]
+    fn test_load_csv_errors() {
+        let load = |csv: &str| Bank::<Decimal>::new().load_csv_dated(csv.as_bytes());
+        let error = |line, kind| Err(CsvError { line, kind });
+        assert_eq!(
+            error(2, CsvErrorKind::FieldCount(3)),
+            load("2024-01-31,USD,CHF,0.9\n2024-01-31,USD,CHF\n")
+        );
+        assert_eq!(
+            error(1, CsvErrorKind::InvalidDate("2024-13-01".to_string())),
+            load("2024-13-01,USD,CHF,0.9")
+        );
+        assert_eq!(
+            error(1, CsvErrorKind::UnknownCurrency("XYZ".to_string())),
+            load("2024-01-31,XYZ,CHF,0.9")
+        );
+        assert_eq!(
+            error(1, CsvErrorKind::InvalidRate("0.9x".to_string())),
+            load("2024-01-31,USD,CHF,0.9x")
+        );
+        assert_eq!(
+            error(2, CsvErrorKind::InvalidRate("0".to_string())),
+            load("2024-01-31,USD,CHF,0.9\n2024-01-31,USD,EUR,0")
+        );
+        assert_eq!(
+            error(1, CsvErrorKind::InvalidRate("-2".to_string())),
+            load("2024-01-31,USD,CHF,-2")
+        );
+        assert_eq!(
+            "line 1: expected 4 fields (date,from,to,rate), found 1",
+            load("nonsense").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_load_csv_conflicts() {
+        let mut bank = Bank::new();
+        bank.add_rate(Currency::USD, Currency::CHF, dec("0.9"));
+        assert_eq!(
+            Err(CsvError {
+                line: 2,
+                kind: CsvErrorKind::Conflict {
+                    from: Currency::USD,
+                    to: Currency::CHF,
+                    existing: dec("0.9"),
+                },
+            }),
+            bank.load_csv("2024-01-31,EUR,USD,1.08\n2024-01-31,USD,CHF,0.95".as_bytes())
+        );
+        assert_eq!(
+            Err(CsvError {
+                line: 2,
+                kind: CsvErrorKind::Conflict {
+                    from: Currency::EUR,
+                    to: Currency::USD,
+                    existing: dec("1.08"),
+                },
+            }),
+            bank.load_csv("2024-01-31,EUR,USD,1.08\n2024-01-31,USD,EUR,0.92".as_bytes())
+        );
+        // Nothing from a rejected file is loaded.
+        assert_eq!(None, bank.rate(Currency::EUR, Currency::USD));
+        assert_eq!(Ok(0), bank.load_csv("2024-01-31,USD,CHF,0.90".as_bytes()));
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let mut bank = Bank::new();
+        bank.add_rate(Currency::USD, Currency::CHF, dec("0.9"));
+        bank.set_rate_at(
+            Currency::EUR,
+            Currency::USD,
+            date("2024-02-01"),
+            dec("1.09"),
+        );
+        bank.set_rate_at(
+            Currency::EUR,
+            Currency::USD,
+            date("2024-01-31"),
+            dec("1.08"),
+        );
+        let mut csv = vec![];
+        bank.export_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            "date,from,to,rate\n\
+             ,USD,CHF,0.9\n\
+             2024-01-31,EUR,USD,1.08\n\
+             2024-02-01,EUR,USD,1.09\n",
+            csv
+        );
+        let mut restored = Bank::<Decimal>::new();
+        assert_eq!(Ok(3), restored.load_csv_dated(csv.as_bytes()));
+        let mut again = vec![];
+        restored.export_csv(&mut again).unwrap();
+        assert_eq!(csv.as_bytes(), &again[..]);
+    }
+
+    #[test]
+    fn test_export_csv_drops_spreads() {
+        let mut bank = Bank::new();
+        bank.set_bid_ask(Currency::CHF, Currency::USD, dec("1.9"), dec("2.1"))
+            .unwrap();
+        let mut csv = vec![];
+        bank.export_csv(&mut csv).unwrap();
+        assert_eq!(&b"date,from,to,rate\n,CHF,USD,2.0\n"[..], &csv[..]);
+        let mut restored = Bank::<Decimal>::new();
+        restored.load_csv(&csv[..]).unwrap();
+        assert_eq!(
+            Some(BidAsk {
+                bid: dec("2"),
+                ask: dec("2"),
+            }),
+            restored.bid_ask(Currency::CHF, Currency::USD)
+        );
+    }
+}
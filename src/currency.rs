@@ -0,0 +1,404 @@
+use crate::{Decimal, Language};
+use std::fmt;
+use std::str::FromStr;
+
+macro_rules! minor_unit {
+    (-) => {
+        None
+    };
+    ($exponent:literal) => {
+        Some($exponent)
+    };
+}
+
+macro_rules! iso4217 {
+    ($($code:ident $numeric:literal $exponent:tt $name:literal;)*) => {
+        /// ISO 4217 currency, identified by its alphabetic code.
+        #[allow(clippy::upper_case_acronyms)]
+        #[derive(Debug, PartialEq, Copy, Clone, Eq, Hash, PartialOrd, Ord)]
+        pub enum Currency {
+            $($code,)*
+        }
+
+        impl Currency {
+            /// Every currency in the registry, ordered by alphabetic code.
+            pub fn all() -> &'static [Currency] {
+                &[$(Currency::$code,)*]
+            }
+
+            /// Alphabetic code, e.g. `"USD"`.
+            pub fn code(self) -> &'static str {
+                match self {
+                    $(Currency::$code => stringify!($code),)*
+                }
+            }
+            /// Numeric code, e.g. `840` for USD.
+            pub fn numeric(self) -> u16 {
+                match self {
+                    $(Currency::$code => $numeric,)*
+                }
+            }
+            /// Number of digits after the decimal separator for the minor unit,
+            /// or `None` when ISO 4217 defines no minor unit (e.g. gold).
+            pub fn exponent(self) -> Option<u32> {
+                match self {
+                    $(Currency::$code => minor_unit!($exponent),)*
+                }
+            }
+            /// English display name, e.g. `"US Dollar"`.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Currency::$code => $name,)*
+                }
+            }
+        }
+    };
+}
+
+iso4217! {
+    AED 784 2 "UAE Dirham";
+    AFN 971 2 "Afghani";
+    ALL 8 2 "Lek";
+    AMD 51 2 "Armenian Dram";
+    AOA 973 2 "Kwanza";
+    ARS 32 2 "Argentine Peso";
+    AUD 36 2 "Australian Dollar";
+    AWG 533 2 "Aruban Florin";
+    AZN 944 2 "Azerbaijan Manat";
+    BAM 977 2 "Convertible Mark";
+    BBD 52 2 "Barbados Dollar";
+    BDT 50 2 "Taka";
+    BGN 975 2 "Bulgarian Lev";
+    BHD 48 3 "Bahraini Dinar";
+    BIF 108 0 "Burundi Franc";
+    BMD 60 2 "Bermudian Dollar";
+    BND 96 2 "Brunei Dollar";
+    BOB 68 2 "Boliviano";
+    BOV 984 2 "Mvdol";
+    BRL 986 2 "Brazilian Real";
+    BSD 44 2 "Bahamian Dollar";
+    BTN 64 2 "Ngultrum";
+    BWP 72 2 "Pula";
+    BYN 933 2 "Belarusian Ruble";
+    BZD 84 2 "Belize Dollar";
+    CAD 124 2 "Canadian Dollar";
+    CDF 976 2 "Congolese Franc";
+    CHE 947 2 "WIR Euro";
+    CHF 756 2 "Swiss Franc";
+    CHW 948 2 "WIR Franc";
+    CLF 990 4 "Unidad de Fomento";
+    CLP 152 0 "Chilean Peso";
+    CNY 156 2 "Yuan Renminbi";
+    COP 170 2 "Colombian Peso";
+    COU 970 2 "Unidad de Valor Real";
+    CRC 188 2 "Costa Rican Colon";
+    CUP 192 2 "Cuban Peso";
+    CVE 132 2 "Cabo Verde Escudo";
+    CZK 203 2 "Czech Koruna";
+    DJF 262 0 "Djibouti Franc";
+    DKK 208 2 "Danish Krone";
+    DOP 214 2 "Dominican Peso";
+    DZD 12 2 "Algerian Dinar";
+    EGP 818 2 "Egyptian Pound";
+    ERN 232 2 "Nakfa";
+    ETB 230 2 "Ethiopian Birr";
+    EUR 978 2 "Euro";
+    FJD 242 2 "Fiji Dollar";
+    FKP 238 2 "Falkland Islands Pound";
+    GBP 826 2 "Pound Sterling";
+    GEL 981 2 "Lari";
+    GHS 936 2 "Ghana Cedi";
+    GIP 292 2 "Gibraltar Pound";
+    GMD 270 2 "Dalasi";
+    GNF 324 0 "Guinean Franc";
+    GTQ 320 2 "Quetzal";
+    GYD 328 2 "Guyana Dollar";
+    HKD 344 2 "Hong Kong Dollar";
+    HNL 340 2 "Lempira";
+    HTG 332 2 "Gourde";
+    HUF 348 2 "Forint";
+    IDR 360 2 "Rupiah";
+    ILS 376 2 "New Israeli Sheqel";
+    INR 356 2 "Indian Rupee";
+    IQD 368 3 "Iraqi Dinar";
+    IRR 364 2 "Iranian Rial";
+    ISK 352 0 "Iceland Krona";
+    JMD 388 2 "Jamaican Dollar";
+    JOD 400 3 "Jordanian Dinar";
+    JPY 392 0 "Yen";
+    KES 404 2 "Kenyan Shilling";
+    KGS 417 2 "Som";
+    KHR 116 2 "Riel";
+    KMF 174 0 "Comorian Franc";
+    KPW 408 2 "North Korean Won";
+    KRW 410 0 "Won";
+    KWD 414 3 "Kuwaiti Dinar";
+    KYD 136 2 "Cayman Islands Dollar";
+    KZT 398 2 "Tenge";
+    LAK 418 2 "Lao Kip";
+    LBP 422 2 "Lebanese Pound";
+    LKR 144 2 "Sri Lanka Rupee";
+    LRD 430 2 "Liberian Dollar";
+    LSL 426 2 "Loti";
+    LYD 434 3 "Libyan Dinar";
+    MAD 504 2 "Moroccan Dirham";
+    MDL 498 2 "Moldovan Leu";
+    MGA 969 2 "Malagasy Ariary";
+    MKD 807 2 "Denar";
+    MMK 104 2 "Kyat";
+    MNT 496 2 "Tugrik";
+    MOP 446 2 "Pataca";
+    MRU 929 2 "Ouguiya";
+    MUR 480 2 "Mauritius Rupee";
+    MVR 462 2 "Rufiyaa";
+    MWK 454 2 "Malawi Kwacha";
+    MXN 484 2 "Mexican Peso";
+    MXV 979 2 "Mexican Unidad de Inversion (UDI)";
+    MYR 458 2 "Malaysian Ringgit";
+    MZN 943 2 "Mozambique Metical";
+    NAD 516 2 "Namibia Dollar";
+    NGN 566 2 "Naira";
+    NIO 558 2 "Cordoba Oro";
+    NOK 578 2 "Norwegian Krone";
+    NPR 524 2 "Nepalese Rupee";
+    NZD 554 2 "New Zealand Dollar";
+    OMR 512 3 "Rial Omani";
+    PAB 590 2 "Balboa";
+    PEN 604 2 "Sol";
+    PGK 598 2 "Kina";
+    PHP 608 2 "Philippine Peso";
+    PKR 586 2 "Pakistan Rupee";
+    PLN 985 2 "Zloty";
+    PYG 600 0 "Guarani";
+    QAR 634 2 "Qatari Rial";
+    RON 946 2 "Romanian Leu";
+    RSD 941 2 "Serbian Dinar";
+    RUB 643 2 "Russian Ruble";
+    RWF 646 0 "Rwanda Franc";
+    SAR 682 2 "Saudi Riyal";
+    SBD 90 2 "Solomon Islands Dollar";
+    SCR 690 2 "Seychelles Rupee";
+    SDG 938 2 "Sudanese Pound";
+    SEK 752 2 "Swedish Krona";
+    SGD 702 2 "Singapore Dollar";
+    SHP 654 2 "Saint Helena Pound";
+    SLE 925 2 "Leone";
+    SOS 706 2 "Somali Shilling";
+    SRD 968 2 "Surinam Dollar";
+    SSP 728 2 "South Sudanese Pound";
+    STN 930 2 "Dobra";
+    SVC 222 2 "El Salvador Colon";
+    SYP 760 2 "Syrian Pound";
+    SZL 748 2 "Lilangeni";
+    THB 764 2 "Baht";
+    TJS 972 2 "Somoni";
+    TMT 934 2 "Turkmenistan New Manat";
+    TND 788 3 "Tunisian Dinar";
+    TOP 776 2 "Pa'anga";
+    TRY 949 2 "Turkish Lira";
+    TTD 780 2 "Trinidad and Tobago Dollar";
+    TWD 901 2 "New Taiwan Dollar";
+    TZS 834 2 "Tanzanian Shilling";
+    UAH 980 2 "Hryvnia";
+    UGX 800 0 "Uganda Shilling";
+    USD 840 2 "US Dollar";
+    USN 997 2 "US Dollar (Next day)";
+    UYI 940 0 "Uruguay Peso en Unidades Indexadas (UI)";
+    UYU 858 2 "Peso Uruguayo";
+    UYW 927 4 "Unidad Previsional";
+    UZS 860 2 "Uzbekistan Sum";
+    VED 926 2 "Bolivar Soberano (Digital)";
+    VES 928 2 "Bolivar Soberano";
+    VND 704 0 "Dong";
+    VUV 548 0 "Vatu";
+    WST 882 2 "Tala";
+    XAF 950 0 "CFA Franc BEAC";
+    XAG 961 - "Silver";
+    XAU 959 - "Gold";
+    XBA 955 - "Bond Markets Unit European Composite Unit (EURCO)";
+    XBB 956 - "Bond Markets Unit European Monetary Unit (E.M.U.-6)";
+    XBC 957 - "Bond Markets Unit European Unit of Account 9 (E.U.A.-9)";
+    XBD 958 - "Bond Markets Unit European Unit of Account 17 (E.U.A.-17)";
+    XCD 951 2 "East Caribbean Dollar";
+    XCG 532 2 "Caribbean Guilder";
+    XDR 960 - "SDR (Special Drawing Right)";
+    XOF 952 0 "CFA Franc BCEAO";
+    XPD 964 - "Palladium";
+    XPF 953 0 "CFP Franc";
+    XPT 962 - "Platinum";
+    XSU 994 - "Sucre";
+    XTS 963 - "Codes specifically reserved for testing purposes";
+    XUA 965 - "ADB Unit of Account";
+    XXX 999 - "No currency";
+    YER 886 2 "Yemeni Rial";
+    ZAR 710 2 "Rand";
+    ZMW 967 2 "Zambian Kwacha";
+    ZWG 924 2 "Zimbabwe Gold";
+}
+
+#[allow(non_upper_case_globals)]
+impl Currency {
+    /// Alias for `USD`, kept from the original two-currency enum.
+    pub const Doller: Currency = Currency::USD;
+    /// Alias for `CHF`.
+    pub const Franc: Currency = Currency::CHF;
+
+    /// Looks up a currency by alphabetic code, ignoring ASCII case.
+    pub fn from_code(code: &str) -> Option<Currency> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|c| c.code().eq_ignore_ascii_case(code))
+    }
+    /// Looks up a currency by numeric code.
+    pub fn from_numeric(numeric: u16) -> Option<Currency> {
+        Self::all().iter().copied().find(|c| c.numeric() == numeric)
+    }
+    /// Smallest amount payable in cash when it is coarser than the minor
+    /// unit, e.g. 0.05 for CHF since the 1 and 2 centime coins were withdrawn.
+    pub fn cash_increment(self) -> Option<Decimal> {
+        let (mantissa, scale) = match self {
+            Currency::AUD | Currency::CAD | Currency::CHF => (5, 2),
+            Currency::NZD => (10, 2),
+            Currency::DKK => (50, 2),
+            Currency::CZK | Currency::NOK | Currency::SEK => (1, 0),
+            Currency::HUF => (5, 0),
+            _ => return None,
+        };
+        Some(Decimal::new(mantissa, scale))
+    }
+    /// Names of the major and minor units in `language`, for the currencies
+    /// that have well-known ones.
+    pub fn unit_names(self, language: Language) -> Option<UnitNames> {
+        let names = |major, major_plural, minor: Option<(&'static str, &'static str)>| {
+            Some(UnitNames {
+                major: (major, major_plural),
+                minor,
+            })
+        };
+        match language {
+            Language::English => match self {
+                Currency::USD
+                | Currency::CAD
+                | Currency::AUD
+                | Currency::NZD
+                | Currency::HKD
+                | Currency::SGD => names("dollar", "dollars", Some(("cent", "cents"))),
+                Currency::EUR => names("euro", "euros", Some(("cent", "cents"))),
+                Currency::GBP => names("pound", "pounds", Some(("penny", "pence"))),
+                Currency::CHF => names("franc", "francs", Some(("centime", "centimes"))),
+                Currency::JPY => names("yen", "yen", None),
+                Currency::KRW => names("won", "won", None),
+                Currency::CNY => names("yuan", "yuan", Some(("fen", "fen"))),
+                Currency::INR => names("rupee", "rupees", Some(("paisa", "paise"))),
+                Currency::MXN => names("peso", "pesos", Some(("centavo", "centavos"))),
+                Currency::SEK => names("krona", "kronor", Some(("öre", "öre"))),
+                Currency::NOK | Currency::DKK => names("krone", "kroner", Some(("øre", "øre"))),
+                Currency::KWD | Currency::BHD => names("dinar", "dinars", Some(("fils", "fils"))),
+                _ => None,
+            },
+            Language::Japanese => match self {
+                Currency::JPY => names("円", "円", None),
+                Currency::USD => names("ドル", "ドル", Some(("セント", "セント"))),
+                Currency::EUR => names("ユーロ", "ユーロ", Some(("セント", "セント"))),
+                Currency::GBP => names("ポンド", "ポンド", Some(("ペンス", "ペンス"))),
+                Currency::CHF => names(
+                    "スイスフラン",
+                    "スイスフラン",
+                    Some(("サンチーム", "サンチーム")),
+                ),
+                Currency::CNY => names("元", "元", Some(("分", "分"))),
+                Currency::KRW => names("ウォン", "ウォン", None),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Singular and plural names of a currency's units in one language.
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+pub struct UnitNames {
+    pub major: (&'static str, &'static str),
+    /// `None` when the currency has no named minor unit, e.g. JPY.
+    pub minor: Option<(&'static str, &'static str)>,
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.code())
+    }
+}
+
+/// Returned when a string is not an ISO 4217 alphabetic code.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseCurrencyError(String);
+
+impl fmt::Display for ParseCurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown currency {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCurrencyError {}
+
+impl FromStr for Currency {
+    type Err = ParseCurrencyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Currency::from_code(s).ok_or_else(|| ParseCurrencyError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_by_code() {
+        assert_eq!(Some(Currency::USD), Currency::from_code("USD"));
+        assert_eq!(Some(Currency::CHF), Currency::from_code("chf"));
+        assert_eq!(None, Currency::from_code("ABC"));
+    }
+
+    #[test]
+    fn test_lookup_by_numeric() {
+        assert_eq!(Some(Currency::JPY), Currency::from_numeric(392));
+        assert_eq!(Some(Currency::ALL), Currency::from_numeric(8));
+        assert_eq!(None, Currency::from_numeric(1));
+    }
+
+    #[test]
+    fn test_metadata() {
+        assert_eq!("USD", Currency::Doller.code());
+        assert_eq!(Currency::CHF, Currency::Franc);
+        assert_eq!(Some(2), Currency::USD.exponent());
+        assert_eq!(Some(0), Currency::JPY.exponent());
+        assert_eq!(Some(3), Currency::KWD.exponent());
+        assert_eq!(None, Currency::XAU.exponent());
+        assert_eq!("Swiss Franc", Currency::CHF.name());
+    }
+
+    #[test]
+    fn test_display_and_parse() {
+        assert_eq!("JPY", Currency::JPY.to_string());
+        assert_eq!(Ok(Currency::EUR), "EUR".parse());
+        assert!("EURO".parse::<Currency>().is_err());
+    }
+
+    #[test]
+    fn test_cash_increment() {
+        assert_eq!(Some(Decimal::new(5, 2)), Currency::CHF.cash_increment());
+        assert_eq!(Some(Decimal::new(1, 0)), Currency::SEK.cash_increment());
+        assert_eq!(None, Currency::USD.cash_increment());
+    }
+
+    #[test]
+    fn test_registry_is_consistent() {
+        for (i, currency) in Currency::all().iter().enumerate() {
+            assert_eq!(Some(*currency), Currency::from_code(currency.code()));
+            assert_eq!(Some(*currency), Currency::from_numeric(currency.numeric()));
+            if i > 0 {
+                assert!(Currency::all()[i - 1].code() < currency.code());
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Calendar date in the proleptic Gregorian calendar.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash, PartialOrd, Ord)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Date { year, month, day })
+    }
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+    /// Days since 1970-01-01, negative before it.
+    pub fn days_since_epoch(&self) -> i64 {
+        // Howard Hinnant's days_from_civil.
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Returned when a string is not a valid `YYYY-MM-DD` date.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseDateError(String);
+
+impl fmt::Display for ParseDateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid date {:?}, expected YYYY-MM-DD", self.0)
+    }
+}
+
+impl std::error::Error for ParseDateError {}
+
+impl FromStr for Date {
+    type Err = ParseDateError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseDateError(s.to_string());
+        let mut parts = s.splitn(3, '-');
+        let mut next = |len: usize| {
+            parts
+                .next()
+                .filter(|part| part.len() == len && part.bytes().all(|b| b.is_ascii_digit()))
+                .ok_or_else(error)
+        };
+        let year = next(4)?.parse().map_err(|_| error())?;
+        let month = next(2)?.parse().map_err(|_| error())?;
+        let day = next(2)?.parse().map_err(|_| error())?;
+        Date::new(year, month, day).ok_or_else(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation() {
+        assert!(Date::new(2024, 2, 29).is_some());
+        assert!(Date::new(2023, 2, 29).is_none());
+        assert!(Date::new(2024, 13, 1).is_none());
+        assert!(Date::new(2024, 4, 31).is_none());
+    }
+
+    #[test]
+    fn test_days_since_epoch() {
+        assert_eq!(0, Date::new(1970, 1, 1).unwrap().days_since_epoch());
+        assert_eq!(-1, Date::new(1969, 12, 31).unwrap().days_since_epoch());
+        assert_eq!(19_783, Date::new(2024, 3, 1).unwrap().days_since_epoch());
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let date: Date = "2024-01-31".parse().unwrap();
+        assert_eq!(Date::new(2024, 1, 31).unwrap(), date);
+        assert_eq!("2024-01-31", date.to_string());
+        assert!("2024-1-31".parse::<Date>().is_err());
+        assert!("2024-02-30".parse::<Date>().is_err());
+    }
+}
@@ -0,0 +1,525 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// How to round when dropping digits.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Default)]
+pub enum RoundingMode {
+    /// To nearest, ties to the even neighbour (banker's rounding).
+    #[default]
+    HalfEven,
+    /// To nearest, ties away from zero.
+    HalfUp,
+    /// To nearest, ties towards zero.
+    HalfDown,
+    /// Away from zero.
+    Up,
+    /// Towards zero.
+    Down,
+    /// Towards positive infinity.
+    Ceiling,
+    /// Towards negative infinity.
+    Floor,
+}
+
+/// Fixed-point base-10 number: `mantissa * 10^-scale`.
+///
+/// Addition and subtraction are exact, as is multiplication up to
+/// `Decimal::MAX_SCALE` fractional digits. Division rounds half to even at
+/// `Decimal::DIV_SCALE` fractional digits, or at a chosen scale with
+/// `div_with_scale`. Values compare equal regardless of scale, so `1.50 == 1.5`.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Fractional digits kept by the `/` operator.
+    pub const DIV_SCALE: u32 = 18;
+    /// Fractional digits kept at most by `*` and `/`, however fine the
+    /// operands.
+    pub const MAX_SCALE: u32 = 28;
+    pub const ZERO: Decimal = Decimal::new(0, 0);
+    pub const ONE: Decimal = Decimal::new(1, 0);
+
+    /// `Decimal::new(1234, 2)` is `12.34`.
+    pub const fn new(mantissa: i128, scale: u32) -> Self {
+        Decimal { mantissa, scale }
+    }
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+    /// Same value with exactly `scale` fractional digits, rounding half to
+    /// even when digits are dropped.
+    pub fn rescale(self, scale: u32) -> Self {
+        self.round(scale, RoundingMode::HalfEven)
+    }
+    /// Same value with exactly `scale` fractional digits, rounding with `mode`
+    /// when digits are dropped.
+    pub fn round(self, scale: u32, mode: RoundingMode) -> Self {
+        if scale >= self.scale {
+            let mantissa = checked(self.mantissa.checked_mul(pow10(scale - self.scale)));
+            Decimal::new(mantissa, scale)
+        } else {
+            let mantissa = div_rounded(self.mantissa, pow10(self.scale - scale), mode);
+            Decimal::new(mantissa, scale)
+        }
+    }
+    /// Nearest multiple of `increment` in the direction given by `mode`, e.g.
+    /// `1.03` to `1.05` with an increment of `0.05` and `HalfUp`.
+    pub fn round_to_increment(self, increment: Self, mode: RoundingMode) -> Self {
+        let (mantissa, step, scale) = self.aligned(increment);
+        let multiple = div_rounded(mantissa, step, mode);
+        Decimal::new(checked(multiple.checked_mul(step)), scale).trim(increment.scale)
+    }
+    /// Drops trailing fractional zeros, keeping at least `min_scale` digits.
+    pub fn trim(self, min_scale: u32) -> Self {
+        let mut trimmed = self;
+        while trimmed.scale > min_scale && trimmed.mantissa % 10 == 0 {
+            trimmed = Decimal::new(trimmed.mantissa / 10, trimmed.scale - 1);
+        }
+        trimmed
+    }
+    /// Quotient rounded half to even at `scale` fractional digits.
+    ///
+    /// # Panics
+    ///
+    /// If the quotient doesn't fit at that scale.
+    pub fn div_with_scale(self, rhs: Self, scale: u32) -> Self {
+        self.quotient(rhs, scale).rescale(scale)
+    }
+    /// Quotient rounded half to even at up to `scale` fractional digits, or
+    /// as many as fit.
+    fn quotient(self, rhs: Self, scale: u32) -> Self {
+        let (lhs, rhs) = (self.trim(0), rhs.trim(0));
+        let digits = i64::from(scale) + i64::from(rhs.scale) - i64::from(lhs.scale);
+        if digits < 0 {
+            let denominator = checked(rhs.mantissa.checked_mul(pow10(-digits as u32)));
+            let (mantissa, _) = divide(lhs.mantissa, denominator, 0, RoundingMode::HalfEven);
+            return Decimal::new(mantissa, scale);
+        }
+        let (mantissa, digits) = divide(
+            lhs.mantissa,
+            rhs.mantissa,
+            digits as u32,
+            RoundingMode::HalfEven,
+        );
+        // Fewer digits than the divisor's scale means an integer part too
+        // large to represent.
+        let scale = (digits + lhs.scale).checked_sub(rhs.scale);
+        Decimal::new(mantissa, scale.expect("decimal overflow"))
+    }
+    fn aligned(self, rhs: Self) -> (i128, i128, u32) {
+        let scale = self.scale.max(rhs.scale);
+        let lhs = checked(self.mantissa.checked_mul(pow10(scale - self.scale)));
+        let rhs = checked(rhs.mantissa.checked_mul(pow10(scale - rhs.scale)));
+        (lhs, rhs, scale)
+    }
+}
+
+fn pow10(exponent: u32) -> i128 {
+    checked(10i128.checked_pow(exponent))
+}
+
+fn checked(value: Option<i128>) -> i128 {
+    value.expect("decimal overflow")
+}
+
+fn div_rounded(numerator: i128, denominator: i128, mode: RoundingMode) -> i128 {
+    divide(numerator, denominator, 0, mode).0
+}
+
+/// Long division of `numerator` by `denominator`, with up to `scale`
+/// fractional digits but no more than fit in the quotient, rounded with
+/// `mode`. Returns the quotient's mantissa and its number of fractional
+/// digits.
+fn divide(numerator: i128, denominator: i128, scale: u32, mode: RoundingMode) -> (i128, u32) {
+    let negative = (numerator < 0) != (denominator < 0);
+    let denominator = denominator.unsigned_abs();
+    let mut quotient = numerator.unsigned_abs() / denominator;
+    let mut remainder = numerator.unsigned_abs() % denominator;
+    let mut digits = 0;
+    while digits < scale {
+        let next = quotient.checked_mul(10).zip(remainder.checked_mul(10));
+        let (shifted, carried) = match next {
+            Some(next) => next,
+            None => break,
+        };
+        let next = shifted + carried / denominator;
+        // Keep room for rounding up.
+        if next >= i128::MAX as u128 {
+            break;
+        }
+        quotient = next;
+        remainder = carried % denominator;
+        digits += 1;
+    }
+    let half = (remainder * 2).cmp(&denominator);
+    let away = remainder != 0
+        && match mode {
+            RoundingMode::HalfEven => {
+                half == Ordering::Greater || (half == Ordering::Equal && quotient % 2 == 1)
+            }
+            RoundingMode::HalfUp => half != Ordering::Less,
+            RoundingMode::HalfDown => half == Ordering::Greater,
+            RoundingMode::Up => true,
+            RoundingMode::Down => false,
+            RoundingMode::Ceiling => !negative,
+            RoundingMode::Floor => negative,
+        };
+    if away {
+        quotient += 1;
+    }
+    let magnitude = checked(i128::try_from(quotient).ok());
+    (if negative { -magnitude } else { magnitude }, digits)
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    /// Compares integer parts, then fractional parts, so that no mantissa
+    /// needs scaling up past what fits.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (lhs_integer, lhs_fraction) = self.split();
+        let (rhs_integer, rhs_fraction) = other.split();
+        lhs_integer
+            .cmp(&rhs_integer)
+            .then_with(|| cmp_fractions(lhs_fraction, rhs_fraction))
+    }
+}
+
+impl Decimal {
+    /// Integer part and fractional part, both with the sign of `self`.
+    fn split(self) -> (i128, Self) {
+        match 10i128.checked_pow(self.scale) {
+            Some(unit) => (
+                self.mantissa / unit,
+                Decimal::new(self.mantissa % unit, self.scale),
+            ),
+            None => (0, self),
+        }
+    }
+}
+
+fn cmp_fractions(lhs: Decimal, rhs: Decimal) -> Ordering {
+    let (coarse, fine, ordering) = if lhs.scale <= rhs.scale {
+        (lhs, rhs, Ordering::Less)
+    } else {
+        (rhs, lhs, Ordering::Greater)
+    };
+    let scaled = 10i128
+        .checked_pow(fine.scale - coarse.scale)
+        .and_then(|unit| coarse.mantissa.checked_mul(unit));
+    let coarse_first = match scaled {
+        Some(scaled) => scaled.cmp(&fine.mantissa),
+        // Past what an `i128` holds, so further from zero than `fine`.
+        None => coarse.mantissa.cmp(&0),
+    };
+    if ordering == Ordering::Less {
+        coarse_first
+    } else {
+        coarse_first.reverse()
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let trimmed = self.trim(0);
+        trimmed.mantissa.hash(state);
+        trimmed.scale.hash(state);
+    }
+}
+
+impl Add for Decimal {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let (lhs, rhs, scale) = self.aligned(rhs);
+        Decimal::new(checked(lhs.checked_add(rhs)), scale)
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        let (lhs, rhs, scale) = self.aligned(rhs);
+        Decimal::new(checked(lhs.checked_sub(rhs)), scale)
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Self;
+    /// Exact up to `Decimal::MAX_SCALE` fractional digits, past which the
+    /// product rounds half to even. Operands too precise for their product
+    /// to fit lose their last digits first.
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, rhs: Self) -> Self::Output {
+        let (mut lhs, mut rhs) = (self, rhs);
+        let product = loop {
+            if let Some(mantissa) = lhs.mantissa.checked_mul(rhs.mantissa) {
+                break Decimal::new(mantissa, lhs.scale + rhs.scale);
+            }
+            // Too many digits for an exact product: give up the last digit
+            // of the finer operand, which is a zero after trimming if any.
+            let finer = if lhs.scale >= rhs.scale {
+                &mut lhs
+            } else {
+                &mut rhs
+            };
+            assert!(finer.scale > 0, "decimal overflow");
+            *finer = finer.rescale(finer.scale - 1);
+        };
+        if product.scale > Self::MAX_SCALE {
+            product.rescale(Self::MAX_SCALE)
+        } else {
+            product
+        }
+    }
+}
+
+impl Div for Decimal {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self::Output {
+        let scale = Self::DIV_SCALE
+            .max(self.scale)
+            .max(rhs.scale)
+            .min(Self::MAX_SCALE);
+        self.quotient(rhs, scale).trim(self.scale.max(rhs.scale))
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Decimal::new(-self.mantissa, self.scale)
+    }
+}
+
+macro_rules! from_integer {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Decimal {
+            fn from(value: $t) -> Self {
+                Decimal::new(i128::from(value), 0)
+            }
+        })*
+    };
+}
+
+from_integer!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl fmt::Display for Decimal {
+    /// An explicit precision rounds half to even, e.g. `{:.2}`, or pads
+    /// with zeros. Width, fill, alignment and `+` apply as for integers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scale = self.scale as usize;
+        let (value, zeros) = match f.precision() {
+            Some(precision) if precision < scale => (self.rescale(precision as u32), 0),
+            Some(precision) => (*self, precision - scale),
+            None => (*self, 0),
+        };
+        let digits = value.mantissa.unsigned_abs().to_string();
+        let scale = value.scale as usize;
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        let number = if fraction.is_empty() && zeros == 0 {
+            integer.to_string()
+        } else {
+            format!("{}.{}{}", integer, fraction, "0".repeat(zeros))
+        };
+        f.pad_integral(value.mantissa >= 0, "", &number)
+    }
+}
+
+/// Returned when a string is not a plain decimal number such as `-12.34`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseDecimalError(String);
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid decimal {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseDecimalError(s.to_string());
+        let (negative, unsigned) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (integer, fraction) = match unsigned.find('.') {
+            Some(dot) => (&unsigned[..dot], &unsigned[dot + 1..]),
+            None => (unsigned, ""),
+        };
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+            return Err(error());
+        }
+        if unsigned.ends_with('.') {
+            return Err(error());
+        }
+        let mantissa: i128 = format!("{}{}", integer, fraction)
+            .parse()
+            .map_err(|_| error())?;
+        let mantissa = if negative { -mantissa } else { mantissa };
+        Ok(Decimal::new(mantissa, fraction.len() as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::dec;
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(Decimal::new(-1234, 2), dec("-12.34"));
+        assert_eq!("-12.34", dec("-12.34").to_string());
+        assert_eq!("0.05", dec("0.05").to_string());
+        assert_eq!("7", dec("+7").to_string());
+        assert_eq!("2.50", format!("{:.2}", dec("2.5")));
+        assert_eq!("2.12", format!("{:.2}", dec("2.125")));
+        assert_eq!(
+            format!("1.{}", "0".repeat(40)),
+            format!("{:.40}", Decimal::ONE)
+        );
+        let large = Decimal::new(i128::MAX, 0);
+        assert_eq!(format!("{}.00", i128::MAX), format!("{:.2}", large));
+        assert_eq!("       1", format!("{:>8}", Decimal::ONE));
+        assert_eq!("-0001.50", format!("{:08.2}", dec("-1.5")));
+        assert_eq!("+1.5**", format!("{:*<+6}", dec("1.5")));
+        assert!("1.".parse::<Decimal>().is_err());
+        assert!(".5".parse::<Decimal>().is_err());
+        assert!("1,5".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn test_equality_ignores_scale() {
+        assert_eq!(dec("1.50"), dec("1.5"));
+        assert!(dec("-0.1") < dec("0.01"));
+        assert_eq!(Decimal::ZERO, Decimal::default());
+        let large = Decimal::new(10i128.pow(30), 0);
+        assert_ne!(large, Decimal::new(1, 18));
+        assert!(large > Decimal::new(1, 18));
+        assert!(-large < Decimal::new(-1, 18));
+        assert!(Decimal::new(1, 40) < dec("0.1"));
+        assert!(Decimal::new(-1, 40) > dec("-0.1"));
+        assert_eq!(Decimal::new(10i128.pow(30), 40), Decimal::new(1, 10));
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        assert_eq!(dec("0.3"), dec("0.1") + dec("0.2"));
+        assert_eq!(dec("-0.1"), dec("0.2") - dec("0.3"));
+        assert_eq!("1.1025", (dec("1.05") * dec("1.05")).to_string());
+    }
+
+    #[test]
+    fn test_product_scale_is_capped() {
+        let third = dec("1") / dec("3");
+        let cubed = third * third * third;
+        assert_eq!(Decimal::MAX_SCALE, cubed.scale());
+        assert_eq!("0.0370370370370370369259629630", cubed.to_string());
+        let mut amount = dec("1000000");
+        for _ in 0..10 {
+            amount = amount * third / third;
+        }
+        assert!(amount.scale() <= Decimal::MAX_SCALE);
+        assert_eq!(dec("1000000"), amount.rescale(2));
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let round = |s: &str, mode| dec(s).round(0, mode).to_string();
+        let cases = [
+            (RoundingMode::HalfEven, ["2", "2", "-2", "3", "-3"]),
+            (RoundingMode::HalfUp, ["3", "2", "-3", "3", "-3"]),
+            (RoundingMode::HalfDown, ["2", "2", "-2", "3", "-3"]),
+            (RoundingMode::Up, ["3", "3", "-3", "3", "-3"]),
+            (RoundingMode::Down, ["2", "2", "-2", "2", "-2"]),
+            (RoundingMode::Ceiling, ["3", "3", "-2", "3", "-2"]),
+            (RoundingMode::Floor, ["2", "2", "-3", "2", "-3"]),
+        ];
+        for (mode, expected) in cases.iter() {
+            let actual: Vec<_> = ["2.5", "2.1", "-2.5", "2.7", "-2.7"]
+                .iter()
+                .map(|s| round(s, *mode))
+                .collect();
+            assert_eq!(expected.to_vec(), actual, "{:?}", mode);
+        }
+        assert_eq!("3.50", dec("3.5").round(2, RoundingMode::Down).to_string());
+    }
+
+    #[test]
+    fn test_round_to_increment() {
+        let nickel = dec("0.05");
+        let round = |s: &str, mode| dec(s).round_to_increment(nickel, mode).to_string();
+        assert_eq!("1.05", round("1.03", RoundingMode::HalfUp));
+        assert_eq!("1.00", round("1.02", RoundingMode::HalfUp));
+        assert_eq!("1.05", round("1.025", RoundingMode::HalfUp));
+        assert_eq!("1.00", round("1.025", RoundingMode::HalfDown));
+        assert_eq!("-1.05", round("-1.03", RoundingMode::HalfEven));
+        assert_eq!("1.00", round("1.04", RoundingMode::Floor));
+        assert_eq!(
+            "10",
+            dec("12")
+                .round_to_increment(dec("5"), RoundingMode::HalfUp)
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_division_rounds_half_even() {
+        assert_eq!("0.333333333333333333", (dec("1") / dec("3")).to_string());
+        assert_eq!("0.666666666666666667", (dec("2") / dec("3")).to_string());
+        assert_eq!("1.00", (dec("2.00") / dec("2")).to_string());
+        assert_eq!("0.12", dec("0.25").div_with_scale(dec("2"), 2).to_string());
+        assert_eq!(
+            "-0.38",
+            dec("-0.75").div_with_scale(dec("2"), 2).to_string()
+        );
+        assert_eq!("3", dec("10").div_with_scale(dec("3"), 0).to_string());
+    }
+
+    #[test]
+    fn test_division_without_overflow() {
+        let third = dec("1") / dec("3");
+        assert_eq!("3000.000000000000003000", (dec("1000") / third).to_string());
+        let amount = dec("12345678901234567890.12");
+        assert_eq!(
+            "37037036703703703707.397036703703703707",
+            (amount / third).to_string()
+        );
+        assert_eq!(
+            "-0.500000000000000000000000",
+            (dec("-1") / dec("2.000000000000000000000000")).to_string()
+        );
+        let fine = Decimal::new(1, 40);
+        assert_eq!(Decimal::MAX_SCALE, (fine / dec("3")).scale());
+    }
+}
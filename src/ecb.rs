@@ -0,0 +1,245 @@
+use crate::{Bank, Currency, Date};
+use std::error::Error;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::ops::{Add, Div, Mul};
+use std::str::FromStr;
+
+/// What is wrong with an ECB reference rate file.
+#[derive(Debug, PartialEq, Clone)]
+pub enum EcbErrorKind {
+    /// The reader failed; holds the I/O error message.
+    Io(String),
+    UnterminatedTag,
+    MissingAttribute(&'static str),
+    /// A currency's rate isn't inside a `<Cube time='...'>` element.
+    RateOutsideDay,
+    InvalidDate(String),
+    InvalidRate(String),
+    NoRates,
+}
+
+/// Returned by `Bank::load_ecb_xml`; `line` counts from 1.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EcbError {
+    pub line: usize,
+    pub kind: EcbErrorKind,
+}
+
+impl fmt::Display for EcbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            EcbErrorKind::Io(message) => write!(f, "{}", message),
+            EcbErrorKind::UnterminatedTag => write!(f, "unterminated tag"),
+            EcbErrorKind::MissingAttribute(name) => write!(f, "missing attribute {:?}", name),
+            EcbErrorKind::RateOutsideDay => write!(f, "rate outside a dated Cube"),
+            EcbErrorKind::InvalidDate(date) => write!(f, "invalid date {:?}", date),
+            EcbErrorKind::InvalidRate(rate) => write!(f, "invalid rate {:?}", rate),
+            EcbErrorKind::NoRates => write!(f, "no reference rates found"),
+        }
+    }
+}
+
+impl Error for EcbError {}
+
+/// Value of attribute `name` in the text of a tag, quoted either way.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(i) = rest.find(name) {
+        let preceded_by_space = rest[..i].ends_with(char::is_whitespace);
+        rest = &rest[i + name.len()..];
+        let value = match rest.trim_start().strip_prefix('=') {
+            Some(value) if preceded_by_space => value.trim_start(),
+            _ => continue,
+        };
+        let quote = value.chars().next().filter(|&c| c == '\'' || c == '"')?;
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+impl<T> Bank<T>
+where
+    T: Copy
+        + Add<Output = T>
+        + Default
+        + From<u8>
+        + Mul<Output = T>
+        + Div<Output = T>
+        + PartialOrd
+        + FromStr,
+{
+    /// Loads an ECB `eurofxref-daily.xml` or `eurofxref-hist.xml` file. Each
+    /// day's rates become effective-dated rates against EUR, in the sense of
+    /// `set_rate_at(currency, Currency::EUR, day, rate)`, and the latest
+    /// day's also replace the plain rates. Currencies the registry doesn't
+    /// know, such as those retired on joining the euro, are skipped. Returns
+    /// how many dated rates were loaded; nothing is loaded from an invalid
+    /// file, including one with a rate that isn't positive.
+    pub fn load_ecb_xml(&mut self, reader: impl Read) -> Result<usize, EcbError> {
+        let mut reader = BufReader::new(reader);
+        let mut xml = String::new();
+        let mut lines = 0;
+        loop {
+            match reader.read_line(&mut xml) {
+                Ok(0) => break,
+                Ok(_) => lines += 1,
+                Err(e) => {
+                    return Err(EcbError {
+                        line: lines + 1,
+                        kind: EcbErrorKind::Io(e.to_string()),
+                    })
+                }
+            }
+        }
+        let mut rates: Vec<(Date, Currency, T)> = vec![];
+        let mut day = None;
+        let mut line = 1;
+        let mut position = 0;
+        while let Some(start) = xml[position..].find('<').map(|i| position + i) {
+            line += xml[position..start].matches('\n').count();
+            let error = |kind| EcbError { line, kind };
+            let end = xml[start..]
+                .find('>')
+                .map(|i| start + i)
+                .ok_or_else(|| error(EcbErrorKind::UnterminatedTag))?;
+            let tag = &xml[start + 1..end];
+            if tag.starts_with("/Cube") {
+                day = None;
+            } else if tag.starts_with("Cube") {
+                let required = |name| {
+                    attribute(tag, name).ok_or_else(|| error(EcbErrorKind::MissingAttribute(name)))
+                };
+                if let Some(time) = attribute(tag, "time") {
+                    day = Some(
+                        time.parse::<Date>()
+                            .map_err(|_| error(EcbErrorKind::InvalidDate(time.to_string())))?,
+                    );
+                } else if let Some(code) = attribute(tag, "currency") {
+                    let date = day.ok_or_else(|| error(EcbErrorKind::RateOutsideDay))?;
+                    let rate = required("rate")?;
+                    let rate = rate
+                        .parse()
+                        .ok()
+                        .filter(|rate| *rate > T::default())
+                        .ok_or_else(|| error(EcbErrorKind::InvalidRate(rate.to_string())))?;
+                    if let Some(currency) = Currency::from_code(code) {
+                        rates.push((date, currency, rate));
+                    }
+                }
+            }
+            line += tag.matches('\n').count();
+            position = end + 1;
+        }
+        let latest = match rates.iter().map(|&(date, _, _)| date).max() {
+            Some(latest) => latest,
+            None => {
+                return Err(EcbError {
+                    line: lines,
+                    kind: EcbErrorKind::NoRates,
+                })
+            }
+        };
+        for &(date, currency, rate) in &rates {
+            self.set_rate_at(currency, Currency::EUR, date, rate);
+            if date == latest {
+                self.set_rate(currency, Currency::EUR, rate);
+            }
+        }
+        Ok(rates.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{date, dec};
+    use crate::{Decimal, Money};
+    use std::fs::File;
+
+    fn sample(name: &str) -> File {
+        File::open(format!(
+            "{}/tests/data/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_load_daily() {
+        let mut bank = Bank::new();
+        assert_eq!(Ok(30), bank.load_ecb_xml(sample("eurofxref-daily.xml")));
+        assert_eq!(
+            Some((Currency::USD, Currency::EUR, dec("1.0826"))),
+            bank.rate(Currency::USD, Currency::EUR)
+        );
+        assert_eq!(
+            Some((Currency::JPY, Currency::EUR, dec("162.39"))),
+            bank.rate_at(Currency::JPY, Currency::EUR, date("2024-03-01"))
+        );
+        assert_eq!(
+            Ok(Money::new(dec("108.26"), Currency::USD)),
+            bank.reduce(Money::new(dec("100"), Currency::EUR), Currency::USD)
+        );
+    }
+
+    #[test]
+    fn test_load_hist() {
+        let mut bank = Bank::new();
+        assert_eq!(Ok(16), bank.load_ecb_xml(sample("eurofxref-hist.xml")));
+        assert_eq!(
+            Some((Currency::CHF, Currency::EUR, dec("0.9572"))),
+            bank.rate(Currency::CHF, Currency::EUR)
+        );
+        assert_eq!(
+            Some((Currency::CHF, Currency::EUR, dec("0.9547"))),
+            bank.rate_at(Currency::CHF, Currency::EUR, date("2024-02-29"))
+        );
+        assert_eq!(
+            Some((Currency::CHF, Currency::EUR, dec("0.9847"))),
+            bank.rate_at(Currency::CHF, Currency::EUR, date("2023-06-30"))
+        );
+        assert_eq!(
+            None,
+            bank.rate_at(Currency::CHF, Currency::EUR, date("2022-12-29"))
+        );
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let load = |xml: &str| Bank::<Decimal>::new().load_ecb_xml(xml.as_bytes());
+        let error = |line, kind| Err(EcbError { line, kind });
+        assert_eq!(
+            error(2, EcbErrorKind::RateOutsideDay),
+            load("<Cube>\n<Cube currency='USD' rate='1.08'/>\n</Cube>")
+        );
+        assert_eq!(
+            error(3, EcbErrorKind::InvalidRate("1,08".to_string())),
+            load("<Cube>\n<Cube time='2024-03-01'>\n<Cube currency='USD' rate='1,08'/>")
+        );
+        assert_eq!(
+            error(2, EcbErrorKind::InvalidRate("0".to_string())),
+            load("<Cube time='2024-03-01'>\n<Cube currency='USD' rate='0'/>")
+        );
+        assert_eq!(
+            error(2, EcbErrorKind::InvalidRate("-1.08".to_string())),
+            load("<Cube time='2024-03-01'>\n<Cube currency='USD' rate='-1.08'/>")
+        );
+        assert_eq!(
+            error(1, EcbErrorKind::InvalidDate("2024-02-30".to_string())),
+            load("<Cube time=\"2024-02-30\">")
+        );
+        assert_eq!(
+            error(2, EcbErrorKind::MissingAttribute("rate")),
+            load("<Cube time='2024-03-01'>\n<Cube currency='USD'/>")
+        );
+        assert_eq!(
+            error(1, EcbErrorKind::UnterminatedTag),
+            load("<Cube time='2024-03-01'")
+        );
+        assert_eq!(error(2, EcbErrorKind::NoRates), load("<Cube>\n</Cube>\n"));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::Currency;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Why no rate could be had for a pair.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RateError {
+    NotFound,
+    /// The rate source failed; holds its message.
+    Unavailable(String),
+    /// The rate source failed and the cached rate is older than allowed.
+    Stale {
+        age: Duration,
+        max: Duration,
+    },
+}
+
+impl fmt::Display for RateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateError::NotFound => write!(f, "no exchange rate"),
+            RateError::Unavailable(message) => write!(f, "rate source unavailable: {}", message),
+            RateError::Stale { age, max } => write!(
+                f,
+                "cached rate is {}s old, over the {}s limit",
+                age.as_secs(),
+                max.as_secs()
+            ),
+        }
+    }
+}
+
+impl Error for RateError {}
+
+/// A leg of a `Money` that `Bank` had no rate for.
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnconvertedLeg<T> {
+    /// Position of the leg within the reduced `Money`, or within the money an
+    /// `Expression` evaluated to.
+    pub index: usize,
+    pub from: Currency,
+    pub amount: T,
+    pub reason: RateError,
+}
+
+/// Returned by `Bank::reduce` when at least one leg can't be converted.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConversionError<T> {
+    pub to: Currency,
+    pub legs: Vec<UnconvertedLeg<T>>,
+}
+
+impl<T: fmt::Display> fmt::Display for ConversionError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A reason shared by every leg is given once, at the end.
+        let common = match self.legs.split_first() {
+            Some((first, rest)) if rest.iter().all(|leg| leg.reason == first.reason) => {
+                Some(&first.reason)
+            }
+            _ => None,
+        };
+        write!(f, "can't convert")?;
+        for (i, leg) in self.legs.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(
+                f,
+                "{}{} {} (leg {}",
+                separator,
+                leg.amount,
+                leg.from.code(),
+                leg.index
+            )?;
+            match common {
+                Some(_) => write!(f, ")")?,
+                None => write!(f, ": {})", leg.reason)?,
+            }
+        }
+        write!(f, " to {}", self.to.code())?;
+        match common {
+            Some(reason) => write!(f, ": {}", reason),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> Error for ConversionError<T> {}
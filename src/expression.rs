@@ -0,0 +1,85 @@
+use crate::Money;
+use std::ops::{Mul, Neg};
+
+/// A formula over money that is only evaluated when a `Bank` reduces it, so
+/// it can be built once and reduced again whenever rates change.
+pub trait Expression<T> {
+    /// The money the expression stands for, still in its original currencies.
+    fn evaluate(&self) -> Money<T>;
+}
+
+impl<T: Copy> Expression<T> for Money<T> {
+    fn evaluate(&self) -> Money<T> {
+        self.clone()
+    }
+}
+
+impl<T, E: Expression<T> + ?Sized> Expression<T> for &E {
+    fn evaluate(&self) -> Money<T> {
+        (**self).evaluate()
+    }
+}
+
+impl<T, E: Expression<T> + ?Sized> Expression<T> for Box<E> {
+    fn evaluate(&self) -> Money<T> {
+        (**self).evaluate()
+    }
+}
+
+pub struct Sum<T> {
+    augend: Box<dyn Expression<T>>,
+    addend: Box<dyn Expression<T>>,
+}
+
+impl<T> Sum<T> {
+    pub fn new(augend: impl Expression<T> + 'static, addend: impl Expression<T> + 'static) -> Self {
+        Sum {
+            augend: Box::new(augend),
+            addend: Box::new(addend),
+        }
+    }
+}
+
+impl<T> Expression<T> for Sum<T> {
+    fn evaluate(&self) -> Money<T> {
+        self.augend.evaluate() + self.addend.evaluate()
+    }
+}
+
+pub struct Times<T> {
+    multiplicand: Box<dyn Expression<T>>,
+    multiplier: T,
+}
+
+impl<T> Times<T> {
+    pub fn new(multiplicand: impl Expression<T> + 'static, multiplier: T) -> Self {
+        Times {
+            multiplicand: Box::new(multiplicand),
+            multiplier,
+        }
+    }
+}
+
+impl<T: Copy + Mul<Output = T>> Expression<T> for Times<T> {
+    fn evaluate(&self) -> Money<T> {
+        self.multiplicand.evaluate().times(self.multiplier)
+    }
+}
+
+pub struct Negate<T> {
+    operand: Box<dyn Expression<T>>,
+}
+
+impl<T> Negate<T> {
+    pub fn new(operand: impl Expression<T> + 'static) -> Self {
+        Negate {
+            operand: Box::new(operand),
+        }
+    }
+}
+
+impl<T: Neg<Output = T>> Expression<T> for Negate<T> {
+    fn evaluate(&self) -> Money<T> {
+        -self.operand.evaluate()
+    }
+}
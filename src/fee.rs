@@ -0,0 +1,368 @@
+use crate::{Bank, ConversionError, Currency, Expression, Money, Side, UnconvertedLeg};
+use std::collections::HashMap;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A percentage of the amount converted, but at least `minimum`. Both are in
+/// the fee currency of the `FeeSchedule`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Fee<T> {
+    /// In percent, e.g. 1.5 for 1.5%.
+    pub percent: T,
+    pub minimum: T,
+}
+
+impl<T: Default> Fee<T> {
+    pub fn percent(percent: T) -> Self {
+        Fee {
+            percent,
+            minimum: T::default(),
+        }
+    }
+    pub fn fixed(amount: T) -> Self {
+        Fee {
+            percent: T::default(),
+            minimum: amount,
+        }
+    }
+    pub fn with_minimum(self, minimum: T) -> Self {
+        Fee { minimum, ..self }
+    }
+}
+
+impl<T> Fee<T>
+where
+    T: Copy + From<u8> + Mul<Output = T> + Div<Output = T> + PartialOrd,
+{
+    fn charge(&self, amount: T) -> T {
+        let fee = amount * self.percent / T::from(100);
+        if fee < self.minimum {
+            self.minimum
+        } else {
+            fee
+        }
+    }
+}
+
+/// `(at_least, fee)`, ordered by `at_least`.
+type Tiers<T> = Vec<(T, Fee<T>)>;
+
+/// Fees charged on conversions, tiered by the amount converted, with tiers
+/// of their own for particular pairs.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FeeSchedule<T> {
+    currency: Currency,
+    tiers: Tiers<T>,
+    pair_tiers: HashMap<(Currency, Currency), Tiers<T>>,
+}
+
+fn insert_tier<T: PartialOrd>(tiers: &mut Tiers<T>, at_least: T, fee: Fee<T>) {
+    let index = tiers
+        .iter()
+        .position(|(threshold, _)| *threshold > at_least)
+        .unwrap_or(tiers.len());
+    tiers.insert(index, (at_least, fee));
+}
+
+impl<T> FeeSchedule<T>
+where
+    T: Copy + Default + From<u8> + Mul<Output = T> + Div<Output = T> + Neg<Output = T> + PartialOrd,
+{
+    /// An empty schedule charging fees in `currency`.
+    pub fn new(currency: Currency) -> Self {
+        FeeSchedule {
+            currency,
+            tiers: vec![],
+            pair_tiers: HashMap::new(),
+        }
+    }
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+    /// Charges `fee` on conversions worth at least `at_least` in the fee
+    /// currency, up to the next tier. A flat schedule is a single tier at
+    /// zero.
+    pub fn add_tier(&mut self, at_least: T, fee: Fee<T>) {
+        insert_tier(&mut self.tiers, at_least, fee);
+    }
+    /// Like `add_tier`, for conversions from `from` into `to` only. A pair
+    /// with tiers of its own doesn't use the schedule-wide ones.
+    pub fn add_pair_tier(&mut self, from: Currency, to: Currency, at_least: T, fee: Fee<T>) {
+        insert_tier(
+            self.pair_tiers.entry((from, to)).or_default(),
+            at_least,
+            fee,
+        );
+    }
+    /// Fee for converting `amount`, worth in the fee currency, from `from`
+    /// into `to`. Charged on the magnitude, so refunds pay fees too.
+    pub fn fee(&self, from: Currency, to: Currency, amount: T) -> T {
+        let amount = if amount < T::default() {
+            -amount
+        } else {
+            amount
+        };
+        let tiers = self.pair_tiers.get(&(from, to)).unwrap_or(&self.tiers);
+        tiers
+            .iter()
+            .rev()
+            .find(|(at_least, _)| *at_least <= amount)
+            .map_or(T::default(), |(_, fee)| fee.charge(amount))
+    }
+}
+
+/// A fee charged on one leg of a `Quote`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct FeeItem<T> {
+    /// Position of the leg in `Money::normalize`'s output, where each
+    /// currency has a single leg.
+    pub index: usize,
+    pub from: Currency,
+    /// The leg's worth in the fee currency, which the fee is based on.
+    pub basis: T,
+    pub fee: T,
+}
+
+/// The outcome of `Bank::quote`.
+#[derive(Debug, Clone)]
+pub struct Quote<T> {
+    /// Everything converted, before fees.
+    pub gross: Money<T>,
+    /// `gross` less the fees, converted into the same currency.
+    pub net: Money<T>,
+    /// The schedule's currency, or the target currency without a schedule.
+    pub fee_currency: Currency,
+    /// One item per converted leg; legs already in the target currency are
+    /// free.
+    pub fees: Vec<FeeItem<T>>,
+}
+
+impl<T> Quote<T>
+where
+    T: Copy + Add<Output = T> + Default,
+{
+    pub fn total_fee(&self) -> Money<T> {
+        let total = self
+            .fees
+            .iter()
+            .fold(T::default(), |total, item| total + item.fee);
+        Money(vec![(self.fee_currency, total)])
+    }
+}
+
+impl<T> Bank<T>
+where
+    T: Copy + Add<Output = T> + Default + From<u8> + Mul<Output = T> + Div<Output = T>,
+{
+    /// Charges `schedule` on conversions quoted by `quote`; `reduce` itself
+    /// stays free of fees.
+    pub fn set_fee_schedule(&mut self, schedule: FeeSchedule<T>) {
+        self.fees = Some(schedule);
+    }
+    /// Converts every leg into `to` at `side`, as `reduce_side`, and deducts
+    /// the fee schedule's fees. Fees are charged per currency after
+    /// normalizing, so equal amounts pay equal fees however their legs are
+    /// split. Each fee is based on the currency's worth in the fee currency
+    /// at the mid rate, and deducted at the mid rate too.
+    pub fn quote<E: Expression<T>>(
+        &self,
+        expression: E,
+        to: Currency,
+        side: Side,
+    ) -> Result<Quote<T>, ConversionError<T>>
+    where
+        T: Sub<Output = T> + Neg<Output = T> + PartialOrd,
+    {
+        let money = expression.evaluate();
+        let gross = self.reduce_side(&money, to, side)?;
+        let schedule = match &self.fees {
+            Some(schedule) => schedule,
+            None => {
+                return Ok(Quote {
+                    net: gross.clone(),
+                    gross,
+                    fee_currency: to,
+                    fees: vec![],
+                })
+            }
+        };
+        let fee_currency = schedule.currency();
+        let mut fees = vec![];
+        let mut legs = vec![];
+        for (index, (from, amount)) in money.normalize().0.into_iter().enumerate() {
+            if from == to {
+                continue;
+            }
+            match self.exchange(amount, from, fee_currency) {
+                Ok(basis) => fees.push(FeeItem {
+                    index,
+                    from,
+                    basis,
+                    fee: schedule.fee(from, to, basis),
+                }),
+                Err(reason) => legs.push(UnconvertedLeg {
+                    index,
+                    from,
+                    amount,
+                    reason,
+                }),
+            }
+        }
+        if !legs.is_empty() {
+            return Err(ConversionError {
+                to: fee_currency,
+                legs,
+            });
+        }
+        let total_fee = fees
+            .iter()
+            .fold(T::default(), |total, item| total + item.fee);
+        let deducted = self.reduce(Money(vec![(fee_currency, total_fee)]), to)?;
+        let net = gross.0[0].1 - deducted.0[0].1;
+        Ok(Quote {
+            net: Money(vec![(to, net)]),
+            gross,
+            fee_currency,
+            fees,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{dec, money};
+    use crate::{Decimal, RateError};
+
+    fn bank() -> Bank<Decimal> {
+        let mut bank = Bank::new();
+        bank.add_rate(Currency::CHF, Currency::USD, dec("2"));
+        bank.add_rate(Currency::EUR, Currency::USD, dec("0.5"));
+        bank
+    }
+
+    fn schedule() -> FeeSchedule<Decimal> {
+        let mut schedule = FeeSchedule::new(Currency::USD);
+        schedule.add_tier(dec("1000"), Fee::percent(dec("0.5")));
+        schedule.add_tier(dec("0"), Fee::percent(dec("1")).with_minimum(dec("2")));
+        schedule
+    }
+
+    #[test]
+    fn test_fee_schedule() {
+        let mut schedule = schedule();
+        let fee = |schedule: &FeeSchedule<Decimal>, amount| {
+            schedule.fee(Currency::CHF, Currency::USD, dec(amount))
+        };
+        assert_eq!(dec("2"), fee(&schedule, "50"));
+        assert_eq!(dec("9.99"), fee(&schedule, "999"));
+        assert_eq!(dec("5"), fee(&schedule, "1000"));
+        assert_eq!(dec("5"), fee(&schedule, "-1000"));
+        schedule.add_pair_tier(Currency::CHF, Currency::USD, dec("0"), Fee::fixed(dec("1")));
+        assert_eq!(dec("1"), fee(&schedule, "5000"));
+        assert_eq!(
+            dec("25"),
+            schedule.fee(Currency::USD, Currency::CHF, dec("5000"))
+        );
+        assert_eq!(
+            dec("0"),
+            FeeSchedule::new(Currency::USD).fee(Currency::CHF, Currency::USD, dec("5000"))
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        let mut bank = bank();
+        bank.set_fee_schedule(schedule());
+        let sum =
+            money("100", Currency::CHF) + money("2400", Currency::EUR) + money("10", Currency::USD);
+        let quote = bank.quote(sum, Currency::USD, Side::Mid).unwrap();
+        assert_eq!(money("4860", Currency::USD), quote.gross);
+        assert_eq!(money("4834", Currency::USD), quote.net);
+        assert_eq!(
+            vec![
+                FeeItem {
+                    index: 0,
+                    from: Currency::CHF,
+                    basis: dec("50"),
+                    fee: dec("2"),
+                },
+                FeeItem {
+                    index: 1,
+                    from: Currency::EUR,
+                    basis: dec("4800"),
+                    fee: dec("24"),
+                },
+            ],
+            quote.fees
+        );
+        assert_eq!(money("26", Currency::USD), quote.total_fee());
+    }
+
+    #[test]
+    fn test_quote_of_split_legs() {
+        let mut bank = bank();
+        bank.set_fee_schedule(schedule());
+        let whole = bank
+            .quote(money("100", Currency::CHF), Currency::USD, Side::Mid)
+            .unwrap();
+        let split = bank
+            .quote(
+                money("50", Currency::CHF) + money("50", Currency::CHF),
+                Currency::USD,
+                Side::Mid,
+            )
+            .unwrap();
+        assert_eq!(money("48", Currency::USD), whole.net);
+        assert_eq!(whole.net, split.net);
+        assert_eq!(whole.fees, split.fees);
+    }
+
+    #[test]
+    fn test_quote_in_other_fee_currency() {
+        let mut bank = bank();
+        let mut schedule = FeeSchedule::new(Currency::EUR);
+        schedule.add_tier(dec("0"), Fee::percent(dec("1")));
+        bank.set_fee_schedule(schedule);
+        let quote = bank
+            .quote(money("100", Currency::CHF), Currency::USD, Side::Mid)
+            .unwrap();
+        assert_eq!(money("0.25", Currency::EUR), quote.total_fee());
+        assert_eq!(money("49.5", Currency::USD), quote.net);
+    }
+
+    #[test]
+    fn test_quote_with_spread() {
+        let mut bank = bank();
+        bank.set_bid_ask(Currency::CHF, Currency::USD, dec("1.6"), dec("2.5"))
+            .unwrap();
+        bank.set_fee_schedule(schedule());
+        let quote = bank
+            .quote(money("100", Currency::CHF), Currency::USD, Side::Buy)
+            .unwrap();
+        assert_eq!(money("40", Currency::USD), quote.gross);
+        assert_eq!(money("38", Currency::USD), quote.net);
+    }
+
+    #[test]
+    fn test_quote_without_schedule() {
+        let quote = bank()
+            .quote(money("100", Currency::CHF), Currency::USD, Side::Mid)
+            .unwrap();
+        assert_eq!(quote.gross, quote.net);
+        assert!(quote.fees.is_empty());
+    }
+
+    #[test]
+    fn test_quote_without_fee_rate() {
+        let mut bank = bank();
+        bank.add_rate(Currency::GBP, Currency::JPY, dec("190"));
+        let mut schedule = FeeSchedule::new(Currency::GBP);
+        schedule.add_tier(dec("0"), Fee::percent(dec("1")));
+        bank.set_fee_schedule(schedule);
+        let error = bank
+            .quote(money("100", Currency::CHF), Currency::USD, Side::Mid)
+            .unwrap_err();
+        assert_eq!(Currency::GBP, error.to);
+        assert_eq!(RateError::NotFound, error.legs[0].reason);
+    }
+}
@@ -0,0 +1,627 @@
+use crate::{Currency, Decimal, Money, RoundingMode};
+use std::fmt;
+use std::str::FromStr;
+
+/// Locales with embedded formatting data, taken from CLDR.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
+pub enum Locale {
+    EnUs,
+    DeCh,
+    DeDe,
+    FrFr,
+    JaJp,
+}
+
+/// Where the minus sign goes when the symbol precedes the number.
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+enum Minus {
+    BeforeSymbol,
+    AfterSymbol,
+}
+
+struct LocaleData {
+    decimal: char,
+    group: char,
+    symbol_first: bool,
+    /// Between the symbol and the number, on whichever side the symbol is.
+    spacing: &'static str,
+    minus: Minus,
+    accounting_parentheses: bool,
+    symbols: &'static [(Currency, &'static str)],
+    /// The locale's own currency and the symbols that denote it there. These
+    /// and `symbols` settle otherwise ambiguous symbols when parsing.
+    home_currency: Currency,
+    home_symbols: &'static [&'static str],
+}
+
+const EN_US: LocaleData = LocaleData {
+    decimal: '.',
+    group: ',',
+    symbol_first: true,
+    spacing: "",
+    minus: Minus::BeforeSymbol,
+    accounting_parentheses: true,
+    symbols: &[
+        (Currency::USD, "$"),
+        (Currency::EUR, "€"),
+        (Currency::GBP, "£"),
+        (Currency::JPY, "¥"),
+        (Currency::CAD, "CA$"),
+        (Currency::AUD, "A$"),
+        (Currency::CNY, "CN¥"),
+    ],
+    home_currency: Currency::USD,
+    home_symbols: &["$", "US$"],
+};
+
+const DE_CH: LocaleData = LocaleData {
+    decimal: '.',
+    group: '\'',
+    symbol_first: true,
+    spacing: " ",
+    minus: Minus::AfterSymbol,
+    accounting_parentheses: false,
+    symbols: &[
+        (Currency::EUR, "€"),
+        (Currency::USD, "$"),
+        (Currency::GBP, "£"),
+        (Currency::JPY, "¥"),
+    ],
+    home_currency: Currency::CHF,
+    home_symbols: &["Fr."],
+};
+
+const DE_DE: LocaleData = LocaleData {
+    decimal: ',',
+    group: '.',
+    symbol_first: false,
+    spacing: " ",
+    minus: Minus::BeforeSymbol,
+    accounting_parentheses: false,
+    symbols: &[
+        (Currency::EUR, "€"),
+        (Currency::USD, "$"),
+        (Currency::GBP, "£"),
+        (Currency::JPY, "¥"),
+    ],
+    home_currency: Currency::EUR,
+    home_symbols: &["€"],
+};
+
+const FR_FR: LocaleData = LocaleData {
+    decimal: ',',
+    group: '\u{202f}',
+    symbol_first: false,
+    spacing: " ",
+    minus: Minus::BeforeSymbol,
+    accounting_parentheses: true,
+    symbols: &[
+        (Currency::EUR, "€"),
+        (Currency::USD, "$US"),
+        (Currency::GBP, "£GB"),
+        (Currency::CAD, "$CA"),
+    ],
+    home_currency: Currency::EUR,
+    home_symbols: &["€"],
+};
+
+const JA_JP: LocaleData = LocaleData {
+    decimal: '.',
+    group: ',',
+    symbol_first: true,
+    spacing: "",
+    minus: Minus::BeforeSymbol,
+    accounting_parentheses: true,
+    symbols: &[
+        (Currency::JPY, "￥"),
+        (Currency::USD, "$"),
+        (Currency::EUR, "€"),
+        (Currency::GBP, "£"),
+        (Currency::CNY, "元"),
+    ],
+    home_currency: Currency::JPY,
+    home_symbols: &["￥", "¥", "円"],
+};
+
+/// Symbols recognised when parsing, with every currency each may denote.
+const SYMBOLS: &[(&str, &[Currency])] = &[
+    (
+        "$",
+        &[
+            Currency::USD,
+            Currency::CAD,
+            Currency::AUD,
+            Currency::NZD,
+            Currency::HKD,
+            Currency::SGD,
+            Currency::MXN,
+        ],
+    ),
+    ("¥", &[Currency::JPY, Currency::CNY]),
+    ("￥", &[Currency::JPY, Currency::CNY]),
+    (
+        "kr",
+        &[Currency::SEK, Currency::NOK, Currency::DKK, Currency::ISK],
+    ),
+    ("€", &[Currency::EUR]),
+    ("£", &[Currency::GBP]),
+    ("₩", &[Currency::KRW]),
+    ("₹", &[Currency::INR]),
+    ("US$", &[Currency::USD]),
+    ("$US", &[Currency::USD]),
+    ("CA$", &[Currency::CAD]),
+    ("$CA", &[Currency::CAD]),
+    ("A$", &[Currency::AUD]),
+    ("£GB", &[Currency::GBP]),
+    ("CN¥", &[Currency::CNY]),
+    ("元", &[Currency::CNY]),
+    ("円", &[Currency::JPY]),
+    ("Fr.", &[Currency::CHF]),
+];
+
+impl Locale {
+    /// BCP 47 tag, e.g. `"de-CH"`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::DeCh => "de-CH",
+            Locale::DeDe => "de-DE",
+            Locale::FrFr => "fr-FR",
+            Locale::JaJp => "ja-JP",
+        }
+    }
+    fn data(self) -> &'static LocaleData {
+        match self {
+            Locale::EnUs => &EN_US,
+            Locale::DeCh => &DE_CH,
+            Locale::DeDe => &DE_DE,
+            Locale::FrFr => &FR_FR,
+            Locale::JaJp => &JA_JP,
+        }
+    }
+    /// Local symbol for `currency`, falling back to its ISO code.
+    pub fn symbol(self, currency: Currency) -> &'static str {
+        self.data()
+            .symbols
+            .iter()
+            .find(|(c, _)| *c == currency)
+            .map_or(currency.code(), |(_, symbol)| symbol)
+    }
+}
+
+impl LocaleData {
+    fn format(
+        &self,
+        locale: Locale,
+        currency: Currency,
+        amount: Decimal,
+        accounting: bool,
+    ) -> String {
+        let amount = match currency.exponent() {
+            Some(exponent) => amount.round(exponent, RoundingMode::HalfEven),
+            None => amount,
+        };
+        let number = self.number(amount);
+        let symbol = locale.symbol(currency);
+        // Codes used as symbols are always set apart from the number.
+        let spacing =
+            if self.spacing.is_empty() && symbol.ends_with(|c: char| c.is_ascii_alphabetic()) {
+                " "
+            } else {
+                self.spacing
+            };
+        let negative = amount < Decimal::ZERO;
+        let parentheses = negative && accounting && self.accounting_parentheses;
+        let minus = if negative && !parentheses { "-" } else { "" };
+        let formatted = match (self.symbol_first, self.minus) {
+            (true, Minus::AfterSymbol) if negative => format!("{}{}{}", symbol, minus, number),
+            (true, _) => format!("{}{}{}{}", minus, symbol, spacing, number),
+            (false, _) => format!("{}{}{}{}", minus, number, spacing, symbol),
+        };
+        if parentheses {
+            format!("({})", formatted)
+        } else {
+            formatted
+        }
+    }
+    /// Absolute value with grouping and the local decimal separator.
+    fn number(&self, amount: Decimal) -> String {
+        let plain = (if amount < Decimal::ZERO {
+            -amount
+        } else {
+            amount
+        })
+        .to_string();
+        let (integer, fraction) = match plain.find('.') {
+            Some(dot) => (&plain[..dot], Some(&plain[dot + 1..])),
+            None => (plain.as_str(), None),
+        };
+        let mut number = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                number.push(self.group);
+            }
+            number.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            number.push(self.decimal);
+            number.push_str(fraction);
+        }
+        number
+    }
+}
+
+impl Money<Decimal> {
+    /// Formats each leg for `locale`, rounded half to even to the currency's
+    /// minor unit, e.g. `$1,234.56`, `1.234,56 €` or `￥1,235`.
+    pub fn format(&self, locale: Locale) -> String {
+        self.format_legs(locale, false)
+    }
+    /// Like `format`, but negative amounts are wrapped in parentheses in
+    /// locales whose accounting style does so, e.g. `($1,234.56)`.
+    pub fn format_accounting(&self, locale: Locale) -> String {
+        self.format_legs(locale, true)
+    }
+    fn format_legs(&self, locale: Locale, accounting: bool) -> String {
+        let data = locale.data();
+        self.0
+            .iter()
+            .map(|&(currency, amount)| data.format(locale, currency, amount, accounting))
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+/// Returned by `Money::parse_localized`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseLocalizedError {
+    MissingAmount,
+    MissingCurrency,
+    /// The number doesn't follow the locale's separators and grouping.
+    InvalidNumber(String),
+    UnknownCurrency(String),
+    /// The symbol denotes several currencies and the locale doesn't settle it.
+    AmbiguousCurrency {
+        symbol: String,
+        candidates: Vec<Currency>,
+    },
+}
+
+impl fmt::Display for ParseLocalizedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseLocalizedError::MissingAmount => write!(f, "no amount found"),
+            ParseLocalizedError::MissingCurrency => write!(f, "no currency found"),
+            ParseLocalizedError::InvalidNumber(number) => write!(f, "invalid number {:?}", number),
+            ParseLocalizedError::UnknownCurrency(symbol) => {
+                write!(f, "unknown currency {:?}", symbol)
+            }
+            ParseLocalizedError::AmbiguousCurrency { symbol, candidates } => {
+                let codes: Vec<_> = candidates.iter().map(|c| c.code()).collect();
+                write!(f, "{:?} could be any of {}", symbol, codes.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseLocalizedError {}
+
+impl LocaleData {
+    fn currency(&self, symbol: &str) -> Result<Currency, ParseLocalizedError> {
+        if let Some(currency) = Currency::from_code(symbol) {
+            return Ok(currency);
+        }
+        if let Some(&(currency, _)) = self.symbols.iter().find(|(_, s)| *s == symbol) {
+            return Ok(currency);
+        }
+        if self.home_symbols.contains(&symbol) {
+            return Ok(self.home_currency);
+        }
+        match SYMBOLS.iter().find(|(s, _)| *s == symbol) {
+            Some((_, [currency])) => Ok(*currency),
+            Some((_, candidates)) => Err(ParseLocalizedError::AmbiguousCurrency {
+                symbol: symbol.to_string(),
+                candidates: candidates.to_vec(),
+            }),
+            None => Err(ParseLocalizedError::UnknownCurrency(symbol.to_string())),
+        }
+    }
+    fn is_group(&self, c: char) -> bool {
+        match self.group {
+            '\'' => c == '\'' || c == '’',
+            '\u{202f}' => c == '\u{202f}' || c == '\u{a0}' || c == ' ',
+            group => c == group,
+        }
+    }
+    /// Plain `-1234.56` form of a number written with this locale's
+    /// separators; groups, if any, must be of three digits.
+    fn unlocalize(&self, number: &str, negative: bool) -> Result<String, ParseLocalizedError> {
+        let error = || ParseLocalizedError::InvalidNumber(number.to_string());
+        let (integer, fraction) = match number.find(self.decimal) {
+            Some(i) => (&number[..i], Some(&number[i + self.decimal.len_utf8()..])),
+            None => (number, None),
+        };
+        let groups: Vec<&str> = integer.split(|c| self.is_group(c)).collect();
+        let grouped_correctly = groups.iter().enumerate().all(|(i, group)| match i {
+            0 => !group.is_empty() && (groups.len() == 1 || group.len() <= 3),
+            _ => group.len() == 3,
+        });
+        let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if !grouped_correctly || !groups.iter().all(|group| digits(group)) {
+            return Err(error());
+        }
+        let mut plain = if negative {
+            "-".to_string()
+        } else {
+            String::new()
+        };
+        plain.push_str(&groups.concat());
+        if let Some(fraction) = fraction {
+            if fraction.is_empty() || !digits(fraction) {
+                return Err(error());
+            }
+            plain.push('.');
+            plain.push_str(fraction);
+        }
+        Ok(plain)
+    }
+}
+
+impl<T: FromStr> Money<T> {
+    /// Reads a single amount written the way `locale` formats it, e.g.
+    /// `1.234,56 €` for `Locale::DeDe`. Negatives may use a minus sign on
+    /// either side of the symbol or accounting parentheses, and ISO codes are
+    /// accepted in place of symbols. Symbols shared by several currencies,
+    /// like `$`, are only accepted where the locale formats or knows one of
+    /// them by it, so whatever `format` writes reads back.
+    pub fn parse_localized(input: &str, locale: Locale) -> Result<Self, ParseLocalizedError> {
+        let data = locale.data();
+        let mut text = input.trim();
+        let mut negative = false;
+        if text.starts_with('(') && text.ends_with(')') {
+            negative = true;
+            text = text[1..text.len() - 1].trim();
+        }
+        let (start, end) = match (
+            text.find(|c: char| c.is_ascii_digit()),
+            text.rfind(|c: char| c.is_ascii_digit()),
+        ) {
+            (Some(start), Some(end)) => (start, end + 1),
+            _ => return Err(ParseLocalizedError::MissingAmount),
+        };
+        let mut affixes = [text[..start].trim(), text[end..].trim()];
+        for affix in affixes.iter_mut() {
+            for minus in ['-', '−'].iter() {
+                if let Some(rest) = affix
+                    .strip_prefix(*minus)
+                    .or_else(|| affix.strip_suffix(*minus))
+                {
+                    if negative {
+                        return Err(ParseLocalizedError::InvalidNumber(input.to_string()));
+                    }
+                    negative = true;
+                    *affix = rest.trim();
+                }
+            }
+        }
+        let symbol = match affixes {
+            ["", ""] => return Err(ParseLocalizedError::MissingCurrency),
+            [symbol, ""] | ["", symbol] => symbol,
+            _ => return Err(ParseLocalizedError::UnknownCurrency(text.to_string())),
+        };
+        let currency = data.currency(symbol)?;
+        let number = data.unlocalize(&text[start..end], negative)?;
+        let amount = number
+            .parse()
+            .map_err(|_| ParseLocalizedError::InvalidNumber(text[start..end].to_string()))?;
+        Ok(Money(vec![(currency, amount)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::money;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            "$1,234.56",
+            money("1234.56", Currency::USD).format(Locale::EnUs)
+        );
+        assert_eq!(
+            "CHF 1'234.55",
+            money("1234.55", Currency::CHF).format(Locale::DeCh)
+        );
+        assert_eq!(
+            "1.234,56 €",
+            money("1234.56", Currency::EUR).format(Locale::DeDe)
+        );
+        assert_eq!(
+            "1\u{202f}234,56 €",
+            money("1234.56", Currency::EUR).format(Locale::FrFr)
+        );
+        assert_eq!(
+            "￥1,235",
+            money("1234.56", Currency::JPY).format(Locale::JaJp)
+        );
+        assert_eq!("$0.50", money("0.5", Currency::USD).format(Locale::EnUs));
+        assert_eq!(
+            "CHF 1,000,000.00",
+            money("1000000", Currency::CHF).format(Locale::EnUs)
+        );
+        assert_eq!(
+            "1.234,560 KWD",
+            money("1234.56", Currency::KWD).format(Locale::DeDe)
+        );
+    }
+
+    #[test]
+    fn test_format_negative() {
+        let amount = money("-1234.56", Currency::USD);
+        assert_eq!("-$1,234.56", amount.format(Locale::EnUs));
+        assert_eq!("($1,234.56)", amount.format_accounting(Locale::EnUs));
+        let amount = money("-1234.55", Currency::CHF);
+        assert_eq!("CHF-1'234.55", amount.format(Locale::DeCh));
+        assert_eq!("CHF-1'234.55", amount.format_accounting(Locale::DeCh));
+        let amount = money("-5", Currency::EUR);
+        assert_eq!("-5,00 €", amount.format(Locale::DeDe));
+        assert_eq!(
+            "(5,00 $US)",
+            money("-5", Currency::USD).format_accounting(Locale::FrFr)
+        );
+        assert_eq!("-￥5", money("-5", Currency::JPY).format(Locale::JaJp));
+    }
+
+    #[test]
+    fn test_format_mixed() {
+        let amount = money("5", Currency::USD) + money("-10", Currency::EUR);
+        assert_eq!("$5.00 + -€10.00", amount.format(Locale::EnUs));
+    }
+
+    #[test]
+    fn test_parse_localized() {
+        let parse = |input: &str, locale| Money::<Decimal>::parse_localized(input, locale);
+        assert_eq!(
+            Ok(money("1234.56", Currency::EUR)),
+            parse("1.234,56 €", Locale::DeDe)
+        );
+        assert_eq!(
+            Ok(money("1234.56", Currency::USD)),
+            parse("$1,234.56", Locale::EnUs)
+        );
+        assert_eq!(
+            Ok(money("1235", Currency::JPY)),
+            parse("¥1,235", Locale::JaJp)
+        );
+        assert_eq!(
+            Ok(money("1235", Currency::JPY)),
+            parse("￥1,235", Locale::JaJp)
+        );
+        assert_eq!(
+            Ok(money("1234.55", Currency::CHF)),
+            parse("CHF 1'234.55", Locale::DeCh)
+        );
+        assert_eq!(
+            Ok(money("1234.56", Currency::EUR)),
+            parse("1 234,56 €", Locale::FrFr)
+        );
+        assert_eq!(Ok(money("5", Currency::CAD)), parse("CA$5", Locale::EnUs));
+        assert_eq!(Ok(money("12", Currency::GBP)), parse(" £12 ", Locale::DeDe));
+        assert_eq!(Ok(money("5", Currency::USD)), parse("5,00 $", Locale::DeDe));
+        assert_eq!(
+            Ok(money("1235", Currency::JPY)),
+            parse("¥1,235", Locale::EnUs)
+        );
+        assert_eq!(Ok(money("5", Currency::USD)), parse("$5.00", Locale::JaJp));
+    }
+
+    #[test]
+    fn test_parse_localized_negative() {
+        let parse = |input: &str, locale| Money::<Decimal>::parse_localized(input, locale);
+        let expected = Ok(money("-1234.56", Currency::USD));
+        assert_eq!(expected, parse("-$1,234.56", Locale::EnUs));
+        assert_eq!(expected, parse("($1,234.56)", Locale::EnUs));
+        assert_eq!(
+            Ok(money("-1234.55", Currency::CHF)),
+            parse("CHF-1'234.55", Locale::DeCh)
+        );
+        assert_eq!(
+            Ok(money("-5", Currency::EUR)),
+            parse("-5,00 €", Locale::DeDe)
+        );
+    }
+
+    #[test]
+    fn test_parse_localized_round_trip() {
+        let locales = [
+            Locale::EnUs,
+            Locale::DeCh,
+            Locale::DeDe,
+            Locale::FrFr,
+            Locale::JaJp,
+        ];
+        let mut currencies: Vec<Currency> = locales
+            .iter()
+            .flat_map(|locale| {
+                let data = locale.data();
+                data.symbols
+                    .iter()
+                    .map(|&(currency, _)| currency)
+                    .chain(Some(data.home_currency))
+            })
+            .collect();
+        currencies.sort_by_key(|currency| currency.code());
+        currencies.dedup();
+        for &currency in &currencies {
+            for amount in ["-1234.56", "1234.56"].iter() {
+                let amount = amount
+                    .parse::<Decimal>()
+                    .unwrap()
+                    .round(currency.exponent().unwrap(), RoundingMode::HalfEven);
+                let amount = Money::new(amount, currency);
+                for &locale in locales.iter() {
+                    for formatted in
+                        [amount.format(locale), amount.format_accounting(locale)].iter()
+                    {
+                        assert_eq!(
+                            Ok(amount.clone()),
+                            Money::parse_localized(formatted, locale),
+                            "{} in {}",
+                            formatted,
+                            locale.tag()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_localized_errors() {
+        let parse = |input: &str, locale| Money::<Decimal>::parse_localized(input, locale);
+        assert_eq!(
+            Err(ParseLocalizedError::AmbiguousCurrency {
+                symbol: "$".to_string(),
+                candidates: vec![
+                    Currency::USD,
+                    Currency::CAD,
+                    Currency::AUD,
+                    Currency::NZD,
+                    Currency::HKD,
+                    Currency::SGD,
+                    Currency::MXN,
+                ],
+            }),
+            parse("5,00 $", Locale::FrFr)
+        );
+        assert!(matches!(
+            parse("1 235 ¥", Locale::FrFr),
+            Err(ParseLocalizedError::AmbiguousCurrency { .. })
+        ));
+        assert_eq!(
+            Err(ParseLocalizedError::InvalidNumber("1.234,56".to_string())),
+            parse("$1.234,56", Locale::EnUs)
+        );
+        assert_eq!(
+            Err(ParseLocalizedError::InvalidNumber("1234.56".to_string())),
+            parse("1234.56 €", Locale::DeDe)
+        );
+        assert_eq!(
+            Err(ParseLocalizedError::InvalidNumber("1,23,456".to_string())),
+            parse("$1,23,456", Locale::EnUs)
+        );
+        assert_eq!(
+            Err(ParseLocalizedError::UnknownCurrency("XYZ".to_string())),
+            parse("XYZ 5", Locale::EnUs)
+        );
+        assert_eq!(
+            Err(ParseLocalizedError::MissingCurrency),
+            parse("1,234", Locale::EnUs)
+        );
+        assert_eq!(
+            Err(ParseLocalizedError::MissingAmount),
+            parse("$", Locale::EnUs)
+        );
+    }
+}
@@ -0,0 +1,433 @@
+use crate::{Currency, Money};
+use std::fmt;
+use std::ops::{Mul, Neg, Range};
+use std::str::FromStr;
+
+/// Why a money expression could not be parsed.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    UnexpectedCharacter(char),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    UnknownCurrency(String),
+    UnclosedParenthesis,
+    /// A plain number where money was needed, e.g. `5 USD + 2`.
+    ExpectedMoney,
+    /// Money multiplied by money, e.g. `5 USD * 2 CHF`.
+    MoneyTimesMoney,
+    /// Parentheses or minus signs nested more than 64 deep.
+    TooDeeplyNested,
+}
+
+/// A `ParseErrorKind` together with the byte range of the input it applies to.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {:?}", c)?,
+            ParseErrorKind::UnexpectedToken(token) => write!(f, "unexpected {:?}", token)?,
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of input")?,
+            ParseErrorKind::InvalidNumber(number) => write!(f, "invalid number {:?}", number)?,
+            ParseErrorKind::UnknownCurrency(code) => write!(f, "unknown currency {:?}", code)?,
+            ParseErrorKind::UnclosedParenthesis => write!(f, "unclosed parenthesis")?,
+            ParseErrorKind::ExpectedMoney => write!(f, "expected money, found a number")?,
+            ParseErrorKind::MoneyTimesMoney => write!(f, "can't multiply money by money")?,
+            ParseErrorKind::TooDeeplyNested => write!(f, "nested too deeply")?,
+        }
+        write!(f, " at {}..{}", self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Token<'a> {
+    Number(&'a str),
+    Word(&'a str),
+    Plus,
+    Minus,
+    Star,
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token<'_>, Range<usize>)>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_digit() || c.is_ascii_alphabetic() => {
+                let numeric = c.is_ascii_digit();
+                let mut end = start + 1;
+                while let Some(&(i, next)) = chars.peek() {
+                    let continues = if numeric {
+                        next.is_ascii_digit() || next == '.'
+                    } else {
+                        next.is_ascii_alphabetic()
+                    };
+                    if !continues {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                let text = &input[start..end];
+                if numeric {
+                    Token::Number(text)
+                } else {
+                    Token::Word(text)
+                }
+            }
+            c => {
+                return Err(ParseError {
+                    kind: ParseErrorKind::UnexpectedCharacter(c),
+                    span: start..start + c.len_utf8(),
+                })
+            }
+        };
+        let end = match token {
+            Token::Number(text) | Token::Word(text) => start + text.len(),
+            _ => start + 1,
+        };
+        tokens.push((token, start..end));
+    }
+    Ok(tokens)
+}
+
+/// How many parentheses and minus signs may enclose each other, so that
+/// parsing user input can't overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+enum Value<T> {
+    Number(T),
+    Money(Money<T>),
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(Token<'a>, Range<usize>)>,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).map(|(token, _)| *token)
+    }
+    fn span(&self) -> Range<usize> {
+        match self.tokens.get(self.position) {
+            Some((_, span)) => span.clone(),
+            None => self.input.len()..self.input.len(),
+        }
+    }
+    fn start(&self) -> usize {
+        self.span().start
+    }
+    fn last_end(&self) -> usize {
+        self.tokens[self.position - 1].1.end
+    }
+    /// Enters the parenthesis or minus sign at the current position.
+    fn nest(&mut self) -> Result<(), ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError {
+                kind: ParseErrorKind::TooDeeplyNested,
+                span: self.span(),
+            });
+        }
+        self.depth += 1;
+        self.position += 1;
+        Ok(())
+    }
+    fn unexpected(&self) -> ParseError {
+        let kind = match self.tokens.get(self.position) {
+            Some((_, span)) => {
+                ParseErrorKind::UnexpectedToken(self.input[span.clone()].to_string())
+            }
+            None => ParseErrorKind::UnexpectedEnd,
+        };
+        ParseError {
+            kind,
+            span: self.span(),
+        }
+    }
+    fn expression<T>(&mut self) -> Result<(Value<T>, Range<usize>), ParseError>
+    where
+        T: Copy + FromStr + Mul<Output = T> + Neg<Output = T>,
+    {
+        let (mut value, mut span) = self.term()?;
+        while let Some(operator @ (Token::Plus | Token::Minus)) = self.peek() {
+            self.position += 1;
+            let lhs = money(value, span.clone())?;
+            let (rhs, rhs_span) = self.term()?;
+            let rhs = money(rhs, rhs_span.clone())?;
+            value = Value::Money(if operator == Token::Plus {
+                lhs + rhs
+            } else {
+                lhs - rhs
+            });
+            span = span.start..rhs_span.end;
+        }
+        Ok((value, span))
+    }
+    fn term<T>(&mut self) -> Result<(Value<T>, Range<usize>), ParseError>
+    where
+        T: Copy + FromStr + Mul<Output = T> + Neg<Output = T>,
+    {
+        let (mut value, mut span) = self.unary()?;
+        while let Some(Token::Star) = self.peek() {
+            self.position += 1;
+            let (rhs, rhs_span) = self.unary()?;
+            let whole = span.start..rhs_span.end;
+            value = match (value, rhs) {
+                (Value::Number(lhs), Value::Number(rhs)) => Value::Number(lhs * rhs),
+                (Value::Money(money), Value::Number(times))
+                | (Value::Number(times), Value::Money(money)) => Value::Money(money.times(times)),
+                (Value::Money(_), Value::Money(_)) => {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::MoneyTimesMoney,
+                        span: whole,
+                    })
+                }
+            };
+            span = whole;
+        }
+        Ok((value, span))
+    }
+    fn unary<T>(&mut self) -> Result<(Value<T>, Range<usize>), ParseError>
+    where
+        T: Copy + FromStr + Mul<Output = T> + Neg<Output = T>,
+    {
+        if let Some(Token::Minus) = self.peek() {
+            let start = self.start();
+            self.nest()?;
+            let (value, span) = self.unary::<T>()?;
+            self.depth -= 1;
+            let value = match value {
+                Value::Number(number) => Value::Number(-number),
+                Value::Money(money) => Value::Money(-money),
+            };
+            return Ok((value, start..span.end));
+        }
+        self.primary()
+    }
+    fn primary<T>(&mut self) -> Result<(Value<T>, Range<usize>), ParseError>
+    where
+        T: Copy + FromStr + Mul<Output = T> + Neg<Output = T>,
+    {
+        let start = self.start();
+        match self.peek() {
+            Some(Token::Open) => {
+                self.nest()?;
+                let (value, _) = self.expression::<T>()?;
+                self.depth -= 1;
+                if self.peek() != Some(Token::Close) {
+                    return Err(match self.peek() {
+                        None => ParseError {
+                            kind: ParseErrorKind::UnclosedParenthesis,
+                            span: start..start + 1,
+                        },
+                        Some(_) => self.unexpected(),
+                    });
+                }
+                self.position += 1;
+                Ok((value, start..self.last_end()))
+            }
+            Some(Token::Number(text)) => {
+                let span = self.span();
+                self.position += 1;
+                let number = text.parse().map_err(|_| ParseError {
+                    kind: ParseErrorKind::InvalidNumber(text.to_string()),
+                    span: span.clone(),
+                })?;
+                match self.peek() {
+                    Some(Token::Word(_)) => {
+                        let currency = self.currency()?;
+                        Ok((
+                            Value::Money(Money::new(number, currency)),
+                            start..self.last_end(),
+                        ))
+                    }
+                    _ => Ok((Value::Number(number), span)),
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+    fn currency(&mut self) -> Result<Currency, ParseError> {
+        let span = self.span();
+        match self.peek() {
+            Some(Token::Word(code)) => {
+                self.position += 1;
+                Currency::from_code(code).ok_or(ParseError {
+                    kind: ParseErrorKind::UnknownCurrency(code.to_string()),
+                    span,
+                })
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+fn money<T>(value: Value<T>, span: Range<usize>) -> Result<Money<T>, ParseError> {
+    match value {
+        Value::Money(money) => Ok(money),
+        Value::Number(_) => Err(ParseError {
+            kind: ParseErrorKind::ExpectedMoney,
+            span,
+        }),
+    }
+}
+
+impl<T> Money<T>
+where
+    T: Copy + FromStr + Mul<Output = T> + Neg<Output = T>,
+{
+    /// Parses a formula such as `"5 USD + 10 CHF * 2 - 3.50 EUR"`.
+    ///
+    /// Amounts are written before their currency code. `*` binds tighter
+    /// than `+` and `-`, parentheses group, and money can only be multiplied
+    /// by plain numbers. Nesting is limited to 64 levels.
+    pub fn parse_expression(input: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            input,
+            tokens: tokenize(input)?,
+            position: 0,
+            depth: 0,
+        };
+        let (value, span) = parser.expression()?;
+        if parser.peek().is_some() {
+            return Err(parser.unexpected());
+        }
+        money(value, span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::dec, Bank, Decimal};
+
+    #[test]
+    fn test_parse_with_precedence() {
+        let money = Money::<Decimal>::parse_expression("5 USD + 10 CHF * 2 - 3.50 EUR").unwrap();
+        assert_eq!(
+            Money::doller(dec("5")) + Money::franc(dec("20"))
+                - Money::new(dec("3.5"), Currency::EUR),
+            money
+        );
+        let money = Money::<i32>::parse_expression("-(5 USD + 1 CHF) * 2 * 3").unwrap();
+        assert_eq!(Money::doller(-30) + Money::franc(-6), money);
+        let money = Money::<i32>::parse_expression("2 * (3 jpy - -1 JPY)").unwrap();
+        assert_eq!(Money::new(8, Currency::JPY), money);
+    }
+
+    #[test]
+    fn test_parsed_money_is_reduced() {
+        let mut bank = Bank::new();
+        bank.add_rate(Currency::Franc, Currency::Doller, 2);
+        let money = Money::parse_expression("5 USD + 10 CHF * 2").unwrap();
+        assert_eq!(
+            Money::doller(15),
+            bank.reduce(money, Currency::USD).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let error = |input: &str| Money::<i32>::parse_expression(input).unwrap_err();
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::InvalidNumber("3.50".to_string()),
+                span: 8..12,
+            },
+            error("5 USD - 3.50 EUR")
+        );
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::UnknownCurrency("XYZ".to_string()),
+                span: 10..13,
+            },
+            error("5 USD + 1 XYZ")
+        );
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::ExpectedMoney,
+                span: 8..13,
+            },
+            error("5 USD + (2*3)")
+        );
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::MoneyTimesMoney,
+                span: 0..13,
+            },
+            error("5 USD * 2 CHF")
+        );
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::UnclosedParenthesis,
+                span: 0..1,
+            },
+            error("(5 USD")
+        );
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::UnexpectedEnd,
+                span: 7..7,
+            },
+            error("5 USD +")
+        );
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::UnexpectedCharacter('/'),
+                span: 6..7,
+            },
+            error("5 USD / 2")
+        );
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::UnexpectedToken(")".to_string()),
+                span: 6..7,
+            },
+            error("5 USD ) + 1 USD")
+        );
+        let nested = format!("{}1 USD{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::TooDeeplyNested,
+                span: MAX_DEPTH..MAX_DEPTH + 1,
+            },
+            error(&nested)
+        );
+        let negated = format!("{}1 USD", "- ".repeat(100_000));
+        assert_eq!(
+            ParseError {
+                kind: ParseErrorKind::TooDeeplyNested,
+                span: 2 * MAX_DEPTH..2 * MAX_DEPTH + 1,
+            },
+            error(&negated)
+        );
+        let within = format!(
+            "{}1 USD{}",
+            "(-".repeat(MAX_DEPTH / 2),
+            ")".repeat(MAX_DEPTH / 2)
+        );
+        assert_eq!(Ok(Money::doller(1)), Money::parse_expression(&within));
+        assert_eq!(
+            "unknown currency \"XYZ\" at 10..13",
+            error("5 USD + 1 XYZ").to_string()
+        );
+    }
+}
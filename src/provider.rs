@@ -0,0 +1,314 @@
+use crate::{Bank, Currency, Date, Hop, RateError};
+use std::collections::HashMap;
+use std::fs::File;
+use std::ops::{Add, Div, Mul};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// A source of exchange rates that `Bank` asks when it has none stored.
+/// Providers are `Send + Sync` so that a bank using one can be shared
+/// across threads.
+pub trait RateProvider<T>: Send + Sync {
+    /// Rate for `from` into `to` in the convention of `Bank::add_rate`,
+    /// effective on `at`, or the current rate when `at` is `None`. A rate
+    /// held the other way round comes back as an inverse `Hop` rather than
+    /// as its reciprocal, so that converting with it stays exact. The hop's
+    /// `from` and `to` must be the ones asked for.
+    fn rate(&self, from: Currency, to: Currency, at: Option<Date>) -> Result<Hop<T>, RateError>;
+}
+
+/// A fixed table of rates, the same whatever the date.
+pub struct StaticRates<T> {
+    rates: HashMap<(Currency, Currency), T>,
+}
+
+impl<T> StaticRates<T> {
+    pub fn new() -> Self {
+        StaticRates {
+            rates: HashMap::new(),
+        }
+    }
+    pub fn add_rate(&mut self, from: Currency, to: Currency, rate: T) {
+        self.rates.insert((from, to), rate);
+    }
+}
+
+impl<T> Default for StaticRates<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> RateProvider<T> for StaticRates<T>
+where
+    T: Copy + From<u8> + Mul<Output = T> + Div<Output = T> + Send + Sync,
+{
+    /// The stored rate, or the inverse of the one stored the other way.
+    fn rate(&self, from: Currency, to: Currency, _at: Option<Date>) -> Result<Hop<T>, RateError> {
+        Hop::find(from, to, &|from, to| self.rates.get(&(from, to)).copied())
+            .ok_or(RateError::NotFound)
+    }
+}
+
+/// Rates read from a `date,from,to,rate` file as written by
+/// `Bank::export_csv`. The file is read on every lookup, so edits are picked
+/// up straight away; dated lookups use its dated rows, current ones its
+/// undated rows.
+pub struct FileRates {
+    path: PathBuf,
+}
+
+impl FileRates {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileRates { path: path.into() }
+    }
+}
+
+impl<T> RateProvider<T> for FileRates
+where
+    T: Copy
+        + Add<Output = T>
+        + Default
+        + From<u8>
+        + Mul<Output = T>
+        + Div<Output = T>
+        + PartialOrd
+        + FromStr
+        + std::fmt::Display,
+{
+    /// A rate stored in the file, or the effective rate of a path through
+    /// several of them.
+    fn rate(&self, from: Currency, to: Currency, at: Option<Date>) -> Result<Hop<T>, RateError> {
+        let unavailable = |message: String| {
+            RateError::Unavailable(format!("{}: {}", self.path.display(), message))
+        };
+        let file = File::open(&self.path).map_err(|e| unavailable(e.to_string()))?;
+        let mut bank = Bank::new();
+        bank.load_csv_dated(file)
+            .map_err(|e| unavailable(e.to_string()))?;
+        let path = match at {
+            Some(date) => bank.conversion_path_at(from, to, date),
+            None => bank.conversion_path(from, to),
+        };
+        let path = path.ok_or(RateError::NotFound)?;
+        match path.hops.as_slice() {
+            [hop] => Ok(*hop),
+            _ => Ok(Hop {
+                from,
+                to,
+                rate: path.rate(),
+                inverse: false,
+            }),
+        }
+    }
+}
+
+/// Asks each provider in turn and answers with the first rate found. When
+/// none has one, a failure of any provider is reported over `NotFound`.
+pub struct ChainedRates<T> {
+    providers: Vec<Box<dyn RateProvider<T>>>,
+}
+
+impl<T> ChainedRates<T> {
+    pub fn new() -> Self {
+        ChainedRates { providers: vec![] }
+    }
+    /// Adds `provider` after those already in the chain.
+    pub fn push(&mut self, provider: impl RateProvider<T> + 'static) {
+        self.providers.push(Box::new(provider));
+    }
+}
+
+impl<T> Default for ChainedRates<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> RateProvider<T> for ChainedRates<T> {
+    fn rate(&self, from: Currency, to: Currency, at: Option<Date>) -> Result<Hop<T>, RateError> {
+        let mut error = RateError::NotFound;
+        for provider in &self.providers {
+            match provider.rate(from, to, at) {
+                Ok(rate) => return Ok(rate),
+                Err(RateError::NotFound) => {}
+                Err(e) if error == RateError::NotFound => error = e,
+                Err(_) => {}
+            }
+        }
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::dec, Decimal, Money};
+
+    struct Failing;
+
+    impl RateProvider<Decimal> for Failing {
+        fn rate(
+            &self,
+            _: Currency,
+            _: Currency,
+            _: Option<Date>,
+        ) -> Result<Hop<Decimal>, RateError> {
+            Err(RateError::Unavailable("timeout".to_string()))
+        }
+    }
+
+    /// Answers every request with a CHF to USD rate.
+    struct Misdirected;
+
+    impl RateProvider<Decimal> for Misdirected {
+        fn rate(
+            &self,
+            _: Currency,
+            _: Currency,
+            _: Option<Date>,
+        ) -> Result<Hop<Decimal>, RateError> {
+            hop(Currency::CHF, Currency::USD, "2", false)
+        }
+    }
+
+    fn hop(
+        from: Currency,
+        to: Currency,
+        rate: &str,
+        inverse: bool,
+    ) -> Result<Hop<Decimal>, RateError> {
+        Ok(Hop {
+            from,
+            to,
+            rate: dec(rate),
+            inverse,
+        })
+    }
+
+    #[test]
+    fn test_static_rates() {
+        let mut rates = StaticRates::new();
+        rates.add_rate(Currency::CHF, Currency::USD, dec("2"));
+        assert_eq!(
+            hop(Currency::CHF, Currency::USD, "2", false),
+            rates.rate(Currency::CHF, Currency::USD, None)
+        );
+        assert_eq!(
+            hop(Currency::USD, Currency::CHF, "2", true),
+            rates.rate(Currency::USD, Currency::CHF, None)
+        );
+        assert_eq!(
+            Err(RateError::NotFound),
+            rates.rate(Currency::EUR, Currency::USD, None)
+        );
+    }
+
+    #[test]
+    fn test_file_rates() {
+        let path = std::env::temp_dir().join(format!("tdd-file-rates-{}.csv", std::process::id()));
+        std::fs::write(&path, ",CHF,USD,2\n2024-01-31,CHF,USD,1.5\n").unwrap();
+        let rates = FileRates::new(&path);
+        let date = Date::new(2024, 2, 1).unwrap();
+        assert_eq!(
+            hop(Currency::CHF, Currency::USD, "2", false),
+            rates.rate(Currency::CHF, Currency::USD, None)
+        );
+        assert_eq!(
+            hop(Currency::CHF, Currency::USD, "1.5", false),
+            rates.rate(Currency::CHF, Currency::USD, Some(date))
+        );
+        std::fs::write(&path, ",CHF,USD,3\n,EUR,USD,0.5\n").unwrap();
+        assert_eq!(
+            hop(Currency::USD, Currency::CHF, "3", true),
+            rates.rate(Currency::USD, Currency::CHF, None)
+        );
+        assert_eq!(
+            hop(Currency::CHF, Currency::EUR, "6", false),
+            rates.rate(Currency::CHF, Currency::EUR, None)
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            RateProvider::<Decimal>::rate(&rates, Currency::CHF, Currency::USD, None),
+            Err(RateError::Unavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_chained_rates() {
+        let mut stub = StaticRates::new();
+        stub.add_rate(Currency::CHF, Currency::USD, dec("2"));
+        let mut chain = ChainedRates::new();
+        chain.push(Failing);
+        chain.push(stub);
+        assert_eq!(
+            hop(Currency::CHF, Currency::USD, "2", false),
+            chain.rate(Currency::CHF, Currency::USD, None)
+        );
+        assert_eq!(
+            Err(RateError::Unavailable("timeout".to_string())),
+            chain.rate(Currency::EUR, Currency::USD, None)
+        );
+        assert_eq!(
+            Err(RateError::NotFound),
+            ChainedRates::<Decimal>::new().rate(Currency::EUR, Currency::USD, None)
+        );
+    }
+
+    #[test]
+    fn test_bank_delegates_to_provider() {
+        let mut stub = StaticRates::new();
+        stub.add_rate(Currency::CHF, Currency::USD, dec("2"));
+        let mut bank = Bank::new();
+        bank.add_rate(Currency::EUR, Currency::USD, dec("0.5"));
+        bank.set_rate_provider(stub);
+        let sum = Money::new(dec("10"), Currency::CHF) + Money::new(dec("1"), Currency::EUR);
+        assert_eq!(
+            Ok(Money::new(dec("7"), Currency::USD)),
+            bank.reduce(sum, Currency::USD)
+        );
+        let date = Date::new(2024, 1, 31).unwrap();
+        assert_eq!(
+            Ok(Money::new(dec("5"), Currency::USD)),
+            bank.reduce_at(Money::new(dec("10"), Currency::CHF), Currency::USD, date)
+        );
+
+        let mut stub = StaticRates::new();
+        stub.add_rate(Currency::CHF, Currency::USD, dec("3"));
+        bank.set_rate_provider(stub);
+        assert_eq!(
+            Ok(Money::new(dec("3000000"), Currency::CHF)),
+            bank.reduce(Money::new(dec("1000000"), Currency::USD), Currency::CHF)
+        );
+
+        bank.set_rate_provider(Failing);
+        let error = bank
+            .reduce(Money::new(dec("10"), Currency::CHF), Currency::USD)
+            .unwrap_err();
+        assert_eq!(
+            RateError::Unavailable("timeout".to_string()),
+            error.legs[0].reason
+        );
+        assert_eq!(
+            "can't convert 10 CHF (leg 0) to USD: rate source unavailable: timeout",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_bank_rejects_rate_for_other_pair() {
+        let mut bank = Bank::new();
+        bank.set_rate_provider(Misdirected);
+        let error = bank
+            .reduce(Money::new(dec("10"), Currency::EUR), Currency::USD)
+            .unwrap_err();
+        assert_eq!(
+            RateError::Unavailable("provider answered for CHF to USD".to_string()),
+            error.legs[0].reason
+        );
+        assert_eq!(
+            Ok(Money::new(dec("5"), Currency::USD)),
+            bank.reduce(Money::new(dec("10"), Currency::CHF), Currency::USD)
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Wire format behind the `serde` feature: currencies as ISO codes, decimals
+//! and dates as strings, money as a list of legs and a versioned rate table
+//! for `Bank`.
+
+use crate::{Bank, Currency, Date, Decimal, Money};
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::ops::{Add, Div, Mul};
+use std::str::FromStr;
+
+/// Written into every serialized rate table; bumped on incompatible changes.
+/// Version 2 added bid/ask quotes; version 1 tables still load.
+const RATE_TABLE_VERSION: u32 = 2;
+
+fn parse<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(de::Error::custom)
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse(deserializer)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse(deserializer)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse(deserializer)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Leg<T> {
+    currency: Currency,
+    amount: T,
+}
+
+impl<T: Serialize> Serialize for Money<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|(currency, amount)| Leg {
+            currency: *currency,
+            amount,
+        }))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Money<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let legs = Vec::<Leg<T>>::deserialize(deserializer)?;
+        Ok(Money(
+            legs.into_iter()
+                .map(|leg| (leg.currency, leg.amount))
+                .collect(),
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RateTable<T> {
+    version: u32,
+    rates: Vec<RateEntry<T>>,
+}
+
+/// A plain rate, or one effective from `date` when present. A plain rate
+/// set with `Bank::set_bid_ask` also carries its quote.
+#[derive(Serialize, Deserialize)]
+struct RateEntry<T> {
+    from: Currency,
+    to: Currency,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date: Option<Date>,
+    rate: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bid: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ask: Option<T>,
+}
+
+impl<T: Serialize + Copy> Serialize for Bank<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let plain = self.rates.iter().map(|(&(from, to), &rate)| {
+            let quote = self.bid_ask.get(&(from, to));
+            RateEntry {
+                from,
+                to,
+                date: None,
+                rate,
+                bid: quote.map(|quote| quote.bid),
+                ask: quote.map(|quote| quote.ask),
+            }
+        });
+        let dated = self.history.iter().flat_map(|(&(from, to), series)| {
+            series.iter().map(move |(&date, &rate)| RateEntry {
+                from,
+                to,
+                date: Some(date),
+                rate,
+                bid: None,
+                ask: None,
+            })
+        });
+        let mut rates: Vec<_> = plain.chain(dated).collect();
+        rates.sort_by_key(|entry| (entry.from, entry.to, entry.date));
+        RateTable {
+            version: RATE_TABLE_VERSION,
+            rates,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Bank<T>
+where
+    T: Deserialize<'de>
+        + Copy
+        + Add<Output = T>
+        + Default
+        + From<u8>
+        + Mul<Output = T>
+        + Div<Output = T>
+        + PartialOrd,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let table = RateTable::<T>::deserialize(deserializer)?;
+        if table.version == 0 || table.version > RATE_TABLE_VERSION {
+            return Err(de::Error::custom(format!(
+                "unsupported rate table version {}",
+                table.version
+            )));
+        }
+        let mut bank = Bank::new();
+        for entry in table.rates {
+            let (from, to) = (entry.from, entry.to);
+            match (entry.date, entry.bid, entry.ask) {
+                (Some(date), None, None) => {
+                    bank.set_rate_at(from, to, date, entry.rate);
+                }
+                (None, None, None) => {
+                    bank.set_rate(from, to, entry.rate);
+                }
+                (None, Some(bid), Some(ask)) => {
+                    bank.set_bid_ask(from, to, bid, ask).map_err(|_| {
+                        de::Error::custom(format!("bid above ask for {} to {}", from, to))
+                    })?;
+                }
+                _ => {
+                    return Err(de::Error::custom(format!(
+                        "incomplete or dated bid/ask quote for {} to {}",
+                        from, to
+                    )))
+                }
+            }
+        }
+        Ok(bank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::dec;
+
+    #[test]
+    fn test_money() {
+        let money = Money::new(dec("5.00"), Currency::USD) + Money::new(dec("-1.5"), Currency::CHF);
+        let json = serde_json::to_string(&money).unwrap();
+        assert_eq!(
+            r#"[{"currency":"USD","amount":"5.00"},{"currency":"CHF","amount":"-1.5"}]"#,
+            json
+        );
+        assert_eq!(money, serde_json::from_str(&json).unwrap());
+        assert!(
+            serde_json::from_str::<Money<Decimal>>(r#"[{"currency":"XYZ","amount":"1"}]"#).is_err()
+        );
+        assert!(
+            serde_json::from_str::<Money<Decimal>>(r#"[{"currency":"USD","amount":1.5}]"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_bank() {
+        let mut bank = Bank::new();
+        bank.add_rate(Currency::USD, Currency::CHF, dec("0.9"));
+        let date = Date::new(2024, 1, 31).unwrap();
+        bank.set_rate_at(Currency::EUR, Currency::USD, date, dec("1.08"));
+        let json = serde_json::to_string(&bank).unwrap();
+        assert_eq!(
+            concat!(
+                r#"{"version":2,"rates":["#,
+                r#"{"from":"EUR","to":"USD","date":"2024-01-31","rate":"1.08"},"#,
+                r#"{"from":"USD","to":"CHF","rate":"0.9"}]}"#
+            ),
+            json
+        );
+        let restored: Bank<Decimal> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            Some((Currency::USD, Currency::CHF, dec("0.9"))),
+            restored.rate(Currency::USD, Currency::CHF)
+        );
+        assert_eq!(
+            Some((Currency::EUR, Currency::USD, dec("1.08"))),
+            restored.rate_at(Currency::EUR, Currency::USD, date)
+        );
+        match serde_json::from_str::<Bank<Decimal>>(r#"{"version":3,"rates":[]}"#) {
+            Err(error) => assert!(error
+                .to_string()
+                .starts_with("unsupported rate table version 3")),
+            Ok(_) => panic!("version 3 accepted"),
+        }
+        let version_1 = r#"{"version":1,"rates":[{"from":"USD","to":"CHF","rate":"0.9"}]}"#;
+        let restored: Bank<Decimal> = serde_json::from_str(version_1).unwrap();
+        assert_eq!(
+            Some((Currency::USD, Currency::CHF, dec("0.9"))),
+            restored.rate(Currency::USD, Currency::CHF)
+        );
+    }
+
+    #[test]
+    fn test_bank_with_spread() {
+        let mut bank = Bank::new();
+        bank.set_bid_ask(Currency::CHF, Currency::USD, dec("1.9"), dec("2.1"))
+            .unwrap();
+        let json = serde_json::to_string(&bank).unwrap();
+        assert_eq!(
+            concat!(
+                r#"{"version":2,"rates":["#,
+                r#"{"from":"CHF","to":"USD","rate":"2.0","bid":"1.9","ask":"2.1"}]}"#
+            ),
+            json
+        );
+        let restored: Bank<Decimal> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            bank.bid_ask(Currency::CHF, Currency::USD),
+            restored.bid_ask(Currency::CHF, Currency::USD)
+        );
+        let inverted = concat!(
+            r#"{"version":2,"rates":["#,
+            r#"{"from":"CHF","to":"USD","rate":"2","bid":"2.1","ask":"1.9"}]}"#
+        );
+        assert!(serde_json::from_str::<Bank<Decimal>>(inverted).is_err());
+        let incomplete = concat!(
+            r#"{"version":2,"rates":["#,
+            r#"{"from":"CHF","to":"USD","rate":"2","bid":"1.9"}]}"#
+        );
+        assert!(serde_json::from_str::<Bank<Decimal>>(incomplete).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{Bank, ConversionError, Currency, Expression, Hop, Money};
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Div, Mul};
+
+/// The two prices of a quote for `(from, to)`, in the convention of
+/// `Bank::add_rate`: how many `from` one `to` is bought (`ask`) or sold
+/// (`bid`) for.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct BidAsk<T> {
+    pub bid: T,
+    pub ask: T,
+}
+
+impl<T> BidAsk<T>
+where
+    T: Copy + Add<Output = T> + From<u8> + Div<Output = T>,
+{
+    pub fn mid(&self) -> T {
+        (self.bid + self.ask) / T::from(2)
+    }
+    /// The quote for `(to, from)`: buying `from` is selling `to`, so each
+    /// side is the reciprocal of the opposite one.
+    pub fn inverse(&self) -> Self {
+        BidAsk {
+            bid: T::from(1) / self.ask,
+            ask: T::from(1) / self.bid,
+        }
+    }
+}
+
+/// Returned by `Bank::set_bid_ask` for a quote whose bid is above its ask.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct InvertedSpread<T> {
+    pub bid: T,
+    pub ask: T,
+}
+
+impl<T: fmt::Display> fmt::Display for InvertedSpread<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bid {} is above ask {}", self.bid, self.ask)
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> Error for InvertedSpread<T> {}
+
+/// Which price of each quote `Bank::reduce_side` converts at.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Default)]
+pub enum Side {
+    /// Buying the target currency at the ask, as a customer converting
+    /// through a dealer pays the spread.
+    Buy,
+    /// Selling the target currency at the bid: the dealer's side of a `Buy`.
+    Sell,
+    /// The mid rate, as `reduce` uses.
+    #[default]
+    Mid,
+}
+
+impl<T> Bank<T>
+where
+    T: Copy + Add<Output = T> + Default + From<u8> + Mul<Output = T> + Div<Output = T>,
+{
+    /// Stores a bid/ask quote for `(from, to)`, replacing a rate or quote
+    /// stored in either direction. The mid becomes the pair's rate. A bid
+    /// above the ask is refused and leaves the bank unchanged.
+    pub fn set_bid_ask(
+        &mut self,
+        from: Currency,
+        to: Currency,
+        bid: T,
+        ask: T,
+    ) -> Result<(), InvertedSpread<T>>
+    where
+        T: PartialOrd,
+    {
+        if bid > ask {
+            return Err(InvertedSpread { bid, ask });
+        }
+        let quote = BidAsk { bid, ask };
+        self.set_rate(from, to, quote.mid());
+        self.bid_ask.insert((from, to), quote);
+        Ok(())
+    }
+    /// Quote for `(from, to)`, derived from the opposite sides when stored
+    /// the other way round. A plain rate reads as a quote without spread.
+    pub fn bid_ask(&self, from: Currency, to: Currency) -> Option<BidAsk<T>> {
+        if let Some(&quote) = self.bid_ask.get(&(from, to)) {
+            return Some(quote);
+        }
+        if let Some(quote) = self.bid_ask.get(&(to, from)) {
+            return Some(quote.inverse());
+        }
+        self.rate(from, to).map(|(stored_from, _, rate)| {
+            let quote = BidAsk {
+                bid: rate,
+                ask: rate,
+            };
+            if stored_from == from {
+                quote
+            } else {
+                quote.inverse()
+            }
+        })
+    }
+    /// Like `reduce`, but each hop converts at the given side of its quote.
+    /// Pairs without a quote, and rates from the provider, have no spread.
+    pub fn reduce_side<E: Expression<T>>(
+        &self,
+        expression: E,
+        to: Currency,
+        side: Side,
+    ) -> Result<Money<T>, ConversionError<T>> {
+        self.reduce_with(expression.evaluate(), to, |amount, from| {
+            match self.conversion_path(from, to) {
+                Some(path) => Ok(path
+                    .hops
+                    .iter()
+                    .fold(amount, |amount, hop| self.apply_side(hop, amount, side))),
+                None => self.provided(amount, from, to, None),
+            }
+        })
+    }
+    fn apply_side(&self, hop: &Hop<T>, amount: T, side: Side) -> T {
+        let stored = if hop.inverse {
+            (hop.to, hop.from)
+        } else {
+            (hop.from, hop.to)
+        };
+        let quote = match self.bid_ask.get(&stored) {
+            Some(quote) => quote,
+            None => return hop.apply(amount),
+        };
+        match (side, hop.inverse) {
+            (Side::Buy, false) => amount / quote.ask,
+            (Side::Sell, false) => amount / quote.bid,
+            (Side::Buy, true) => amount * quote.bid,
+            (Side::Sell, true) => amount * quote.ask,
+            (Side::Mid, _) => hop.apply(amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::dec, Decimal};
+
+    fn chf(amount: &str) -> Money<Decimal> {
+        Money::new(dec(amount), Currency::CHF)
+    }
+
+    fn usd(amount: &str) -> Money<Decimal> {
+        Money::new(dec(amount), Currency::USD)
+    }
+
+    #[test]
+    fn test_bid_ask() {
+        let mut bank = Bank::new();
+        bank.add_rate(Currency::CHF, Currency::USD, dec("3"));
+        bank.set_bid_ask(Currency::CHF, Currency::USD, dec("1.9"), dec("2.1"))
+            .unwrap();
+        assert_eq!(
+            Some((Currency::CHF, Currency::USD, dec("2"))),
+            bank.rate(Currency::CHF, Currency::USD)
+        );
+        assert_eq!(
+            Some(BidAsk {
+                bid: dec("1.9"),
+                ask: dec("2.1"),
+            }),
+            bank.bid_ask(Currency::CHF, Currency::USD)
+        );
+        let inverse = bank.bid_ask(Currency::USD, Currency::CHF).unwrap();
+        assert_eq!(dec("1") / dec("2.1"), inverse.bid);
+        assert_eq!(dec("1") / dec("1.9"), inverse.ask);
+        bank.add_rate(Currency::EUR, Currency::USD, dec("0.5"));
+        assert_eq!(
+            Some(BidAsk {
+                bid: dec("0.5"),
+                ask: dec("0.5"),
+            }),
+            bank.bid_ask(Currency::EUR, Currency::USD)
+        );
+        bank.remove_rate(Currency::USD, Currency::CHF);
+        assert_eq!(None, bank.bid_ask(Currency::CHF, Currency::USD));
+    }
+
+    #[test]
+    fn test_reduce_side() {
+        let mut bank = Bank::new();
+        bank.set_bid_ask(Currency::CHF, Currency::USD, dec("1.6"), dec("2.5"))
+            .unwrap();
+        assert_eq!(
+            Ok(usd("4")),
+            bank.reduce_side(chf("10"), Currency::USD, Side::Buy)
+        );
+        assert_eq!(
+            Ok(usd("6.25")),
+            bank.reduce_side(chf("10"), Currency::USD, Side::Sell)
+        );
+        assert_eq!(
+            Ok(usd("4.878048780487804878")),
+            bank.reduce_side(chf("10"), Currency::USD, Side::Mid)
+        );
+        assert_eq!(
+            bank.reduce(chf("10"), Currency::USD),
+            bank.reduce_side(chf("10"), Currency::USD, Side::Mid)
+        );
+        // The other way round the customer sells USD at the bid.
+        assert_eq!(
+            Ok(chf("16")),
+            bank.reduce_side(usd("10"), Currency::CHF, Side::Buy)
+        );
+        assert_eq!(
+            Ok(chf("25")),
+            bank.reduce_side(usd("10"), Currency::CHF, Side::Sell)
+        );
+    }
+
+    #[test]
+    fn test_reduce_side_across_pairs() {
+        let mut bank = Bank::new();
+        bank.set_bid_ask(Currency::CHF, Currency::USD, dec("1.6"), dec("2.5"))
+            .unwrap();
+        bank.add_rate(Currency::EUR, Currency::USD, dec("0.5"));
+        assert_eq!(
+            Ok(Money::new(dec("2"), Currency::EUR)),
+            bank.reduce_side(chf("10"), Currency::EUR, Side::Buy)
+        );
+    }
+
+    #[test]
+    fn test_bid_above_ask() {
+        let mut bank = Bank::new();
+        bank.add_rate(Currency::CHF, Currency::USD, dec("2"));
+        let error = bank
+            .set_bid_ask(Currency::CHF, Currency::USD, dec("2.1"), dec("1.9"))
+            .unwrap_err();
+        assert_eq!(
+            InvertedSpread {
+                bid: dec("2.1"),
+                ask: dec("1.9"),
+            },
+            error
+        );
+        assert_eq!("bid 2.1 is above ask 1.9", error.to_string());
+        assert_eq!(
+            Some((Currency::CHF, Currency::USD, dec("2"))),
+            bank.rate(Currency::CHF, Currency::USD)
+        );
+        assert_eq!(None, bank.bid_ask.get(&(Currency::CHF, Currency::USD)));
+    }
+}
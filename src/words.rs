@@ -0,0 +1,256 @@
+use crate::{Currency, Decimal, Money, RoundingMode};
+
+/// Languages `Money::to_words` can spell amounts in.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
+pub enum Language {
+    English,
+    /// Formal daiji numerals as written on cheques and invoices.
+    Japanese,
+}
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const THOUSANDS: [&str; 13] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+    "sextillion",
+    "septillion",
+    "octillion",
+    "nonillion",
+    "decillion",
+    "undecillion",
+];
+
+fn english(n: u128) -> String {
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut groups = vec![];
+    let mut rest = n;
+    let mut scale = 0;
+    while rest > 0 {
+        let group = (rest % 1000) as usize;
+        if group > 0 {
+            let words = english_below_thousand(group);
+            groups.push(match THOUSANDS[scale] {
+                "" => words,
+                name => format!("{} {}", words, name),
+            });
+        }
+        rest /= 1000;
+        scale += 1;
+    }
+    groups.reverse();
+    groups.join(" ")
+}
+
+fn english_below_thousand(n: usize) -> String {
+    let mut words = vec![];
+    if n >= 100 {
+        words.push(format!("{} hundred", ONES[n / 100]));
+    }
+    match n % 100 {
+        0 => {}
+        below_twenty @ 1..=19 => words.push(ONES[below_twenty].to_string()),
+        tens if tens % 10 == 0 => words.push(TENS[tens / 10].to_string()),
+        tens => words.push(format!("{}-{}", TENS[tens / 10], ONES[tens % 10])),
+    }
+    words.join(" ")
+}
+
+const DAIJI_DIGITS: [&str; 10] = ["", "壱", "弐", "参", "四", "伍", "六", "七", "八", "九"];
+const DAIJI_SMALL_UNITS: [&str; 4] = ["", "拾", "百", "千"];
+const DAIJI_LARGE_UNITS: [&str; 10] = ["", "萬", "億", "兆", "京", "垓", "𥝱", "穣", "溝", "澗"];
+
+fn daiji(n: u128) -> String {
+    if n == 0 {
+        return "零".to_string();
+    }
+    let mut groups = vec![];
+    let mut rest = n;
+    let mut scale = 0;
+    while rest > 0 {
+        let group = (rest % 10_000) as usize;
+        if group > 0 {
+            let mut words = String::new();
+            for place in (0..4).rev() {
+                let digit = group / 10usize.pow(place as u32) % 10;
+                if digit > 0 {
+                    words.push_str(DAIJI_DIGITS[digit]);
+                    words.push_str(DAIJI_SMALL_UNITS[place]);
+                }
+            }
+            words.push_str(DAIJI_LARGE_UNITS[scale]);
+            groups.push(words);
+        }
+        rest /= 10_000;
+        scale += 1;
+    }
+    groups.reverse();
+    groups.concat()
+}
+
+fn leg_words(currency: Currency, amount: Decimal, language: Language) -> String {
+    let exponent = currency.exponent().unwrap_or(0);
+    let amount = amount.round(exponent, RoundingMode::HalfEven);
+    let units = amount.mantissa().unsigned_abs();
+    let factor = 10u128.pow(exponent);
+    let (major, minor) = (units / factor, units % factor);
+    let names = currency.unit_names(language);
+    let major_name = names.map_or(currency.code(), |names| {
+        if major == 1 {
+            names.major.0
+        } else {
+            names.major.1
+        }
+    });
+    let minor_name =
+        names.and_then(|names| names.minor).map(
+            |(singular, plural)| {
+                if minor == 1 {
+                    singular
+                } else {
+                    plural
+                }
+            },
+        );
+    let negative = amount < Decimal::ZERO;
+    let mut parts = vec![];
+    match language {
+        Language::English => {
+            if major > 0 || minor == 0 {
+                parts.push(format!("{} {}", english(major), major_name));
+            }
+            if minor > 0 {
+                parts.push(match minor_name {
+                    Some(name) => format!("{} {}", english(minor), name),
+                    None => format!("{}/{}", minor, factor),
+                });
+            }
+            let words = parts.join(" and ");
+            if negative {
+                format!("minus {}", words)
+            } else {
+                words
+            }
+        }
+        Language::Japanese => {
+            if major > 0 || minor == 0 {
+                parts.push(format!("{}{}", daiji(major), major_name));
+            }
+            if minor > 0 {
+                parts.push(match minor_name {
+                    Some(name) => format!("{}{}", daiji(minor), name),
+                    None => format!("{}/{}", minor, factor),
+                });
+            }
+            let sign = if negative { "マイナス" } else { "" };
+            format!("{}金{}", sign, parts.concat())
+        }
+    }
+}
+
+impl Money<Decimal> {
+    /// Spells every leg out in words, rounded half to even to the currency's
+    /// minor unit, e.g. "one hundred twenty-three dollars and forty-five
+    /// cents" or "金壱萬弐千参百円". Currencies without known unit names use
+    /// their ISO code, with the minor unit as a fraction.
+    pub fn to_words(&self, language: Language) -> String {
+        let separator = match language {
+            Language::English => ", ",
+            Language::Japanese => "、",
+        };
+        self.0
+            .iter()
+            .map(|&(currency, amount)| leg_words(currency, amount, language))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::money;
+
+    #[test]
+    fn test_english() {
+        let words = |amount, currency| money(amount, currency).to_words(Language::English);
+        assert_eq!(
+            "one hundred twenty-three dollars and forty-five cents",
+            words("123.45", Currency::USD)
+        );
+        assert_eq!("one dollar and one cent", words("1.01", Currency::USD));
+        assert_eq!("zero euros", words("0", Currency::EUR));
+        assert_eq!("fifty pence", words("0.5", Currency::GBP));
+        assert_eq!(
+            "minus twelve francs and five centimes",
+            words("-12.05", Currency::CHF)
+        );
+        assert_eq!(
+            "one million two thousand three yen",
+            words("1002003.4", Currency::JPY)
+        );
+        assert_eq!(
+            "seven dinars and one hundred twenty-three fils",
+            words("7.123", Currency::KWD)
+        );
+        assert_eq!("ten ZAR and 5/100", words("10.05", Currency::ZAR));
+        assert_eq!(
+            "five hundred eleven sextillion dollars",
+            words("511000000000000000000000", Currency::USD)
+        );
+    }
+
+    #[test]
+    fn test_japanese() {
+        let words = |amount, currency| money(amount, currency).to_words(Language::Japanese);
+        assert_eq!("金壱萬弐千参百円", words("12300", Currency::JPY));
+        assert_eq!("金壱千壱拾円", words("1010", Currency::JPY));
+        assert_eq!("金参億伍萬円", words("300050000", Currency::JPY));
+        assert_eq!("金零円", words("0", Currency::JPY));
+        assert_eq!("マイナス金伍円", words("-5", Currency::JPY));
+        assert_eq!(
+            "金壱百弐拾参ドル四拾伍セント",
+            words("123.45", Currency::USD)
+        );
+        assert_eq!("金壱兆円", words("1000000000000", Currency::JPY));
+    }
+
+    #[test]
+    fn test_mixed() {
+        let amount = money("1", Currency::USD) + money("2", Currency::EUR);
+        assert_eq!("one dollar, two euros", amount.to_words(Language::English));
+        assert_eq!("金壱ドル、金弐ユーロ", amount.to_words(Language::Japanese));
+    }
+}